@@ -0,0 +1,233 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::client::{AuthMethod, Connection};
+use crate::reconnect::ReconnectPolicy;
+#[cfg(feature = "tls")]
+use crate::tls::{Tls, TlsOptions};
+use crate::transport::Uri;
+
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Credentials {
+    pub username: String,
+    pub password: Option<String>,
+    /// advertised by the server if not set
+    pub method: Option<AuthMethod>,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field(
+                "password",
+                &self.password.as_ref().map(|_| format_args!("..")),
+            )
+            .field("method", &self.method)
+            .finish()
+    }
+}
+
+/// Connection settings collected by [`ConnectionBuilder`].
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    pub credentials: Option<Credentials>,
+    pub connect_timeout: Option<Duration>,
+    pub request_timeout: Option<Duration>,
+    pub reconnect: Option<ReconnectPolicy>,
+
+    pub read_buffer_size: usize,
+    pub write_buffer_size: usize,
+    pub request_channel_size: usize,
+    pub batch_size: usize,
+    pub max_frame_size: usize,
+
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive: Option<Duration>,
+
+    pub task_name_prefix: Option<String>,
+
+    #[cfg(feature = "tls")]
+    pub tls: Option<Arc<Tls>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            credentials: None,
+            connect_timeout: None,
+            request_timeout: None,
+            reconnect: None,
+            read_buffer_size: 128 * 1024,
+            write_buffer_size: 128 * 1024,
+            // depends on the thread number
+            request_channel_size: 16 * 1024,
+            batch_size: 1000,
+            max_frame_size: 128 * 1024 * 1024,
+            tcp_nodelay: false,
+            tcp_keepalive: None,
+            task_name_prefix: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn task_name(&self, name: &str) -> String {
+        match &self.task_name_prefix {
+            Some(prefix) => format!("{prefix} {name}"),
+            None => name.to_owned(),
+        }
+    }
+}
+
+/// Configures and opens a [`Connection`].
+///
+/// ```no_run
+/// # async fn example() -> std::io::Result<()> {
+/// use std::time::Duration;
+/// use iproto::client::Connection;
+///
+/// let conn = Connection::builder("localhost:3301")
+///     .credentials("user", Some("secret"))
+///     .connect_timeout(Duration::from_secs(1))
+///     .request_timeout(Duration::from_secs(5))
+///     .connect()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectionBuilder {
+    addr: String,
+    config: Config,
+    auth_method: Option<AuthMethod>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsOptions>,
+}
+
+impl ConnectionBuilder {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            config: Config::default(),
+            auth_method: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Authenticates right after connecting and after every reconnect, before any request
+    /// is sent. A rejected password fails [`connect`](Self::connect) or the reconnect attempt.
+    pub fn credentials(mut self, username: &str, password: Option<&str>) -> Self {
+        self.config.credentials = Some(Credentials {
+            username: username.to_owned(),
+            password: password.map(str::to_owned),
+            method: None,
+        });
+        self
+    }
+
+    /// Overrides the authentication method advertised by the server.
+    /// Required to use `pap-sha256` over a connection without TLS.
+    pub fn auth_method(mut self, method: AuthMethod) -> Self {
+        self.auth_method = Some(method);
+        self
+    }
+
+    /// Limits opening the stream, reading the greeting and the handshake: feature
+    /// negotiation and authentication with the configured credentials. Applies to
+    /// every reconnect attempt as well.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Default limit for every request, requests wait for the response forever if not set.
+    /// Covers [`Connection::auth`], but not the handshake limited by
+    /// [`connect_timeout`](Self::connect_timeout).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Re-dials the server when the connection is lost instead of failing all further requests.
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.config.reconnect = Some(policy);
+        self
+    }
+
+    /// Capacity of the buffer responses are read through.
+    pub fn read_buffer_size(mut self, size: usize) -> Self {
+        self.config.read_buffer_size = size;
+        self
+    }
+
+    /// Capacity of the buffer requests are batched in.
+    pub fn write_buffer_size(mut self, size: usize) -> Self {
+        self.config.write_buffer_size = size;
+        self
+    }
+
+    /// Number of requests that may wait for the writer before callers are suspended, at least 1.
+    pub fn request_channel_size(mut self, size: usize) -> Self {
+        self.config.request_channel_size = size.max(1);
+        self
+    }
+
+    /// The writer keeps batching already queued requests until the batch reaches this size in bytes.
+    /// Smaller values lower latency, larger ones reduce the number of syscalls.
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.config.batch_size = size;
+        self
+    }
+
+    /// Largest response accepted in bytes, 128 MiB by default. A larger length prefix
+    /// is treated as a corrupted stream and closes the connection.
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.config.max_frame_size = size;
+        self
+    }
+
+    /// Sets TCP_NODELAY on the socket.
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.config.tcp_nodelay = nodelay;
+        self
+    }
+
+    /// Enables TCP keepalive probes after the connection has been idle for `idle`.
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.config.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Prefix for the names of spawned tasks, to tell connections apart in tokio-console.
+    pub fn task_name_prefix(mut self, prefix: &str) -> Self {
+        self.config.task_name_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Encrypts the connection, the server must listen with `transport = 'ssl'`.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, options: TlsOptions) -> Self {
+        self.tls = Some(options);
+        self
+    }
+
+    pub async fn connect(mut self) -> std::io::Result<Arc<Connection>> {
+        let uri = Uri::parse(&self.addr)?;
+        if self.config.credentials.is_none() {
+            self.config.credentials = uri.credentials;
+        }
+        if let Some(credentials) = &mut self.config.credentials {
+            credentials.method = self.auth_method;
+        }
+        #[cfg(feature = "tls")]
+        if let Some(options) = &self.tls {
+            self.config.tls = Some(Arc::new(Tls::new(options, &uri.address)?));
+        }
+        Connection::connect_with_config(&uri.address, self.config).await
+    }
+}
@@ -1,221 +1,723 @@
+use std::collections::HashMap;
 use std::io::Cursor;
+use std::pin::Pin;
 use std::sync::{
-    Arc,
-    atomic::{AtomicU8, Ordering},
+    Arc, Mutex, RwLock,
+    atomic::{AtomicU8, AtomicU64, AtomicUsize, Ordering},
 };
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
 
-use base64::Engine;
-use base64::engine::general_purpose::STANDARD as Base64Engine;
-use futures::FutureExt;
-use futures::future::try_join;
+use futures::stream::BoxStream;
+use futures::{FutureExt, Stream, StreamExt, TryStreamExt};
 use nix::sys::socket;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use sharded_slab::{Pool, Slab};
+use sharded_slab::Slab;
+use std::net::SocketAddr;
 use thiserror::Error;
 use tokio::io::{BufReader, BufWriter};
-use tokio::net::{
-    TcpStream, ToSocketAddrs,
-    tcp::{OwnedReadHalf, OwnedWriteHalf},
-};
-use tokio::sync::{Notify, mpsc, oneshot, watch};
 
+use tokio::net::{TcpStream, UnixStream};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Instant;
+
+use crate::builder::{Config, Credentials};
+use crate::iproto::greeting::{GREETING_SIZE, Greeting};
 use crate::iproto::{consts, request, response};
-use crate::utils::SlabEntryGuard;
+use crate::schema::{IndexTuple, SpaceTuple};
+use crate::transport::{Address, Endpoint, ReadHalf, Socket, WriteHalf};
+use crate::utils::{CountGuard, PoolEntryGuard, SlabEntryGuard};
 use request::Request;
 use response::ResponseBody;
 
-const READ_BUFFER: usize = 128 * 1024;
-const WRITE_BUFFER: usize = 128 * 1024;
-
-// depends on the thread number
-const REQ_CHANNEL_BUFFER: usize = 16 * 1024;
+pub use crate::builder::ConnectionBuilder;
+pub use crate::iproto::error_code::TarantoolErrorCode;
+pub use crate::iproto::greeting::ServerVersion;
+pub use crate::iproto::update::{Field, UpdateOps};
+pub use crate::pool::{Mode, Pool, PoolBuilder, Strategy};
+pub use crate::reconnect::ReconnectPolicy;
+pub use crate::schema::{FieldFormat, Index, IndexPart, Schema, Space};
+#[cfg(feature = "tls")]
+pub use crate::tls::TlsOptions;
+pub use request::{After, AuthMethod, Feature, IndexRef, IteratorType, SpaceRef, TxnIsolation};
+pub use response::{
+    ColumnMetadata, DecodeError, ErrorExtra, ErrorResponse, Position, SelectResponse,
+    ServerFeatures, SqlInfo, SqlResponse,
+};
 
 type Buffer = Vec<u8>;
 
 #[derive(Error, Debug, Clone)]
 pub enum Error {
-    #[error("tarantool error")]
-    TarantoolError(response::ErrorResponse),
-    #[error("invalid response")]
-    InvalidResponse,
-    #[error("decoding error")]
-    InvalidDecoding,
+    #[error(transparent)]
+    TarantoolError(Box<ErrorResponse>),
+    #[error("unexpected response code {code:#x} to request {request_type:#04x}")]
+    InvalidResponse { request_type: u8, code: u32 },
+    #[error("failed to decode response to request {request_type:#04x}")]
+    InvalidDecoding {
+        request_type: u8,
+        #[source]
+        source: Arc<response::DecodeError>,
+    },
+    #[error("failed to encode request {request_type:#04x}")]
+    InvalidEncoding {
+        request_type: u8,
+        #[source]
+        source: Arc<rmp_serde::encode::Error>,
+    },
+    #[error("too many pending requests")]
+    TooManyRequests,
     #[error("error")]
     ErrorCode(u8),
     #[error("connection error")]
     ConnectionError(Arc<std::io::Error>),
+    #[error("space {0} not found")]
+    NoSuchSpace(String),
+    #[error("index {index} not found in space {space}")]
+    NoSuchIndex { space: String, index: String },
+    #[error("authentication method {0} is not supported")]
+    UnsupportedAuthMethod(String),
+    #[error("authentication method {0} requires an encrypted connection")]
+    InsecureAuthMethod(AuthMethod),
+    #[error("no healthy instance available for {0:?} requests")]
+    NoAvailableInstance(Mode),
+    #[error("server does not support {0:?}")]
+    UnsupportedFeature(Feature),
+    #[error("connection lost before the response was received")]
+    Disconnected,
+    #[error("request timed out")]
+    Timeout,
+}
+
+impl Error {
+    /// Code of the error returned by the server.
+    pub fn tarantool_code(&self) -> Option<TarantoolErrorCode> {
+        match self {
+            Error::TarantoolError(err) => Some(err.code),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestOptions {
+    /// Whether the request may be sent again if the connection is lost before
+    /// the response is received. Defaults to the request type: only reads are
+    /// retried unless set explicitly. Has no effect without a [`ReconnectPolicy`].
+    pub idempotent: Option<bool>,
+
+    /// Overrides [`ConnectionBuilder::request_timeout`] for the request.
+    pub timeout: Option<Duration>,
 }
 
-#[derive(Debug)]
 struct TarantoolResp {
     header: response::ResponseHeader,
     cursor_ref: CursorRef,
 }
 
-#[derive(Debug)]
 struct CursorRef {
-    buffer_key: usize,
+    /// released when the response is dropped, even if nobody waits for it anymore
+    buffer: PoolEntryGuard<Buffer>,
     position: u64,
 }
 
+type PushSender = futures::channel::mpsc::UnboundedSender<rmpv::Value>;
+
 struct RequestHandle {
-    request_id: usize,
     tx: oneshot::Sender<TarantoolResp>,
+    push_tx: Option<PushSender>,
+}
+
+/// Encoded request waiting for the writer.
+struct PendingWrite {
+    /// cleared once written, or when the write is dropped before reaching the writer
+    buffer: PoolEntryGuard<Buffer>,
+
+    /// pending request the write belongs to, not written if it was cancelled meanwhile
+    request_id: Option<usize>,
+
+    /// connection epoch the request was encoded for,
+    /// requests of the previous epochs are dropped by the writer
+    epoch: u64,
+}
+
+type RequestsReceiver = Arc<tokio::sync::Mutex<mpsc::Receiver<PendingWrite>>>;
+
+/// Server state received during the handshake, refreshed on every reconnect.
+#[derive(Debug, Clone, Default)]
+struct Session {
+    salt: Vec<u8>,
+    server_version: ServerVersion,
+    server_features: ServerFeatures,
 }
 
 pub struct Connection {
     state: AtomicU8,
-    requests_to_process_tx: mpsc::Sender<usize>,
+    requests_to_process_tx: mpsc::Sender<PendingWrite>,
 
     pending_requests: Slab<RequestHandle>,
-    requests_not_full_notify: Notify,
+    /// requests waiting for a response, the slab does not track its size
+    pending_count: AtomicUsize,
 
-    buffer_pool: Arc<Pool<Buffer>>,
+    buffer_pool: Arc<sharded_slab::Pool<Buffer>>,
 
-    salt: Vec<u8>,
-    mss: u32,
+    endpoint: Endpoint,
+
+    config: Config,
+    session: RwLock<Session>,
+    credentials: Mutex<Option<Credentials>>,
+
+    epoch_tx: watch::Sender<u64>,
+
+    next_stream_id: AtomicU64,
+
+    watchers: Mutex<HashMap<String, watch::Sender<Option<rmpv::Value>>>>,
+
+    /// last schema version reported by the server
+    schema_version: AtomicU64,
+    schema: Mutex<Option<Arc<Schema>>>,
+    /// makes concurrent callers wait for a single reload
+    schema_load: tokio::sync::Mutex<()>,
 
     error_rx: watch::Receiver<Option<Error>>,
+
+    /// every write buffer taken from the pool, to check they are all released
+    #[cfg(test)]
+    write_buffer_keys: Mutex<Vec<usize>>,
 }
 
 const DISCONNECTED_STATE: u8 = 0;
 const CONNECTED_STATE: u8 = 1;
 
 impl Connection {
-    pub async fn connect(addr: impl ToSocketAddrs) -> std::io::Result<Arc<Self>> {
-        use tokio::io::AsyncReadExt;
-
-        let stream = TcpStream::connect(addr).await?;
-        let mss = socket::getsockopt(&stream, socket::sockopt::TcpMaxSeg)?;
+    /// Connects with the default settings, see [`Connection::builder`] to change them.
+    ///
+    /// `uri` follows the Tarantool syntax: `host:port`, a bare port on localhost,
+    /// `unix/:/path/to.sock` or an absolute socket path, optionally prefixed
+    /// with `login:password@` to authenticate.
+    pub async fn connect(uri: impl Into<String>) -> std::io::Result<Arc<Self>> {
+        Self::builder(uri).connect().await
+    }
 
-        let (mut read_stream, write_stream) = stream.into_split();
-        let salt = {
-            let mut greeting_raw = [0; 128];
-            read_stream.read_exact(&mut greeting_raw).await?;
-            let salt_b64 = std::str::from_utf8(&greeting_raw[64..108]).unwrap().trim();
+    pub fn builder(addr: impl Into<String>) -> ConnectionBuilder {
+        ConnectionBuilder::new(addr)
+    }
 
-            Base64Engine.decode(salt_b64).unwrap()
-        };
+    pub(crate) async fn connect_with_config(
+        address: &Address,
+        config: Config,
+    ) -> std::io::Result<Arc<Self>> {
+        let endpoint = address.resolve().await?;
+        let (mut stream, greeting) = dial(&endpoint, &config).await?;
 
-        let (requests_to_process_tx, requests_to_process_rx) = mpsc::channel(REQ_CHANNEL_BUFFER);
+        let (requests_to_process_tx, requests_to_process_rx) =
+            mpsc::channel(config.request_channel_size);
         let (error_tx, error_rx) = tokio::sync::watch::channel(None);
 
         let conn = Arc::new(Connection {
-            state: AtomicU8::new(CONNECTED_STATE),
+            state: AtomicU8::new(DISCONNECTED_STATE),
             requests_to_process_tx,
             pending_requests: Slab::new(),
-            requests_not_full_notify: Notify::new(),
-            buffer_pool: Arc::new(Pool::new()),
-            salt,
-            mss,
+            pending_count: AtomicUsize::new(0),
+            buffer_pool: Arc::new(sharded_slab::Pool::new()),
+            endpoint,
+            session: RwLock::new(Session {
+                salt: greeting.salt,
+                server_version: greeting.version,
+                server_features: ServerFeatures::default(),
+            }),
+            credentials: Mutex::new(config.credentials.clone()),
+            config,
+            epoch_tx: watch::Sender::new(0),
+            next_stream_id: AtomicU64::new(1),
+            watchers: Mutex::new(HashMap::new()),
+            schema_version: AtomicU64::new(0),
+            schema: Mutex::new(None),
+            schema_load: tokio::sync::Mutex::new(()),
             error_rx,
+            #[cfg(test)]
+            write_buffer_keys: Mutex::new(Vec::new()),
         });
 
-        let writer_conn = conn.clone();
+        conn.establish_session(&mut stream)
+            .await
+            .map_err(std::io::Error::other)?;
+
+        let supervisor_conn = conn.clone();
+        let requests_to_process_rx = Arc::new(tokio::sync::Mutex::new(requests_to_process_rx));
+        tokio::task::Builder::new()
+            .name(&conn.config.task_name("iproto supervisor"))
+            .spawn(async move {
+                let err = supervisor_conn
+                    .supervise(requests_to_process_rx, stream)
+                    .await;
+                let _ = error_tx.send(Some(Error::ConnectionError(Arc::new(err))));
+            })?;
+
+        Ok(conn)
+    }
+
+    /// Serves the connection and re-dials it after failures if reconnect is enabled.
+    /// Returns the error the connection is finally closed with.
+    async fn supervise(
+        self: Arc<Self>,
+        requests_to_process_rx: RequestsReceiver,
+        mut stream: Socket,
+    ) -> std::io::Error {
+        let mut is_reconnect = false;
+        loop {
+            let err = self
+                .serve(&requests_to_process_rx, stream, is_reconnect)
+                .await;
+            self.state.store(DISCONNECTED_STATE, Ordering::Relaxed);
+
+            let Some(policy) = &self.config.reconnect else {
+                return err;
+            };
+
+            // fails or resends requests waiting for responses from the lost connection
+            self.epoch_tx.send_modify(|epoch| *epoch += 1);
+
+            stream = match self.redial(policy).await {
+                Ok(stream) => stream,
+                Err(err) => return err,
+            };
+            is_reconnect = true;
+        }
+    }
+
+    /// Runs writer and reader on the stream until one of them fails.
+    async fn serve(
+        self: &Arc<Self>,
+        requests_to_process_rx: &RequestsReceiver,
+        stream: Socket,
+        is_reconnect: bool,
+    ) -> std::io::Error {
+        let (read_stream, write_stream) = stream.into_split();
+        self.state.store(CONNECTED_STATE, Ordering::Relaxed);
+
+        let writer_conn = self.clone();
+        let writer_rx = requests_to_process_rx.clone();
         let writer_task = tokio::task::Builder::new()
-            .name("writer")
+            .name(&self.config.task_name("writer"))
             .spawn(async move {
+                let mut requests_to_process_rx = writer_rx.lock().await;
                 writer_conn
-                    .writer(requests_to_process_rx, write_stream)
+                    .writer(&mut requests_to_process_rx, write_stream)
                     .await
-            })?;
+            });
+        let mut writer_task = match writer_task {
+            Ok(writer_task) => writer_task,
+            Err(err) => return err,
+        };
 
-        let reader_conn = conn.clone();
+        let reader_conn = self.clone();
         let reader_task = tokio::task::Builder::new()
-            .name("reader")
-            .spawn(async move { reader_conn.reader(read_stream).await })?;
+            .name(&self.config.task_name("reader"))
+            .spawn(async move { reader_conn.reader(read_stream).await });
+        let mut reader_task = match reader_task {
+            Ok(reader_task) => reader_task,
+            Err(err) => {
+                writer_task.abort();
+                return err;
+            }
+        };
 
-        tokio::task::Builder::new()
-            .name("iproto error catcher")
-            .spawn(async move {
-                match try_join(writer_task, reader_task).await.unwrap() {
-                    (Ok(()), Ok(())) => {}
-                    (Err(err), _) | (_, Err(err)) => {
-                        let _ = error_tx.send(Some(Error::ConnectionError(Arc::new(err))));
+        let restore = async {
+            if is_reconnect {
+                self.restore_session().await
+            } else {
+                Ok(())
+            }
+        };
+        tokio::pin!(restore);
+        let mut is_restored = false;
+
+        let err = loop {
+            tokio::select! {
+                result = &mut writer_task => break task_error(result),
+                result = &mut reader_task => break task_error(result),
+                result = &mut restore, if !is_restored => match result {
+                    Ok(()) => is_restored = true,
+                    Err(err) => break std::io::Error::other(err),
+                },
+            }
+        };
+
+        writer_task.abort();
+        reader_task.abort();
+        err
+    }
+
+    async fn redial(&self, policy: &ReconnectPolicy) -> std::io::Result<Socket> {
+        let mut failed_attempts = 0;
+        loop {
+            tokio::time::sleep(policy.backoff(failed_attempts)).await;
+
+            match self.reopen().await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => {
+                    failed_attempts += 1;
+                    if policy.is_exhausted(failed_attempts) {
+                        return Err(err);
                     }
                 }
-            })?;
+            }
+        }
+    }
 
-        Ok(conn)
+    /// Dials the server again and repeats the handshake with the last used credentials.
+    async fn reopen(&self) -> std::io::Result<Socket> {
+        let (mut stream, greeting) = dial(&self.endpoint, &self.config).await?;
+        {
+            let mut session = self.session.write().unwrap();
+            session.salt = greeting.salt;
+            session.server_version = greeting.version;
+        }
+        self.establish_session(&mut stream)
+            .await
+            .map_err(std::io::Error::other)?;
+        Ok(stream)
+    }
+
+    /// Negotiates features and authenticates on a new stream before writer and reader
+    /// are started on it, so no queued request can reach the server unauthenticated.
+    async fn establish_session(&self, stream: &mut Socket) -> Result<(), Error> {
+        let establish = async {
+            let server_features = match self.exchange(stream, &request::Id::new(0)).await {
+                Ok(server_features) => server_features,
+                // IPROTO_ID is unknown to servers older than 2.10
+                Err(Error::TarantoolError(_)) => ServerFeatures::default(),
+                Err(err) => return Err(err),
+            };
+            self.session.write().unwrap().server_features = server_features;
+
+            let credentials = self.credentials.lock().unwrap().clone();
+            if let Some(Credentials {
+                username,
+                password,
+                method,
+            }) = credentials
+            {
+                let (salt, method) = self.auth_params(method)?;
+                let auth = request::Auth::new(0, method, &salt, &username, password.as_deref());
+                let _resp: response::EmptyResponse = self.exchange(stream, &auth).await?;
+            }
+            Ok(())
+        };
+        with_timeout(self.config.connect_timeout, establish).await
     }
 
-    fn write_req_to_buf<R>(&self, req: &R) -> Result<usize, rmp_serde::encode::Error>
+    /// Sends a request and reads its response on a stream nothing else is reading or writing yet.
+    async fn exchange<Req, Resp>(&self, stream: &mut Socket, req: &Req) -> Result<Resp, Error>
     where
-        R: Request<Buffer>,
+        Req: Request<Buffer>,
+        Resp: ResponseBody,
     {
-        let mut write_buf = self.buffer_pool.create().unwrap();
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let io_error = |err| Error::ConnectionError(Arc::new(err));
+
+        let mut buf = Buffer::new();
+        encode_frame(req, &mut buf)?;
+        stream.write_all(&buf).await.map_err(io_error)?;
+        stream.flush().await.map_err(io_error)?;
+
+        let mut payload_len_raw = [0; 5];
+        stream
+            .read_exact(&mut payload_len_raw)
+            .await
+            .map_err(io_error)?;
+        let len = frame_len(&payload_len_raw, self.config.max_frame_size).map_err(io_error)?;
+        buf.clear();
+        buf.resize(len, 0);
+        stream.read_exact(&mut buf).await.map_err(io_error)?;
+
+        let mut cursor = Cursor::new(&buf);
+        let header = response::ResponseHeader::decode(&mut cursor).map_err(|err| {
+            Error::InvalidDecoding {
+                request_type: Req::REQUEST_TYPE,
+                source: Arc::new(err),
+            }
+        })?;
+        if let Some(schema_version) = header.schema_version {
+            self.schema_version.store(schema_version, Ordering::Relaxed);
+        }
+        decode_response(
+            Req::REQUEST_TYPE,
+            header.response_code_indicator(),
+            &mut cursor,
+        )
+    }
+
+    /// Re-registers watchers on a new connection.
+    async fn restore_session(&self) -> Result<(), Error> {
+        let keys: Vec<String> = self.watchers.lock().unwrap().keys().cloned().collect();
+        for key in keys {
+            self.send_oneway(request::Watch::new(&key)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of requests sent or queued and not answered yet.
+    pub fn pending_requests(&self) -> usize {
+        self.pending_count.load(Ordering::Relaxed)
+    }
+
+    /// Whether the stream is open, `false` while reconnecting or after the connection is closed.
+    pub fn is_connected(&self) -> bool {
+        self.state.load(Ordering::Relaxed) == CONNECTED_STATE
+    }
+
+    pub fn server_version(&self) -> ServerVersion {
+        self.session.read().unwrap().server_version
+    }
+
+    pub fn server_features(&self) -> ServerFeatures {
+        self.session.read().unwrap().server_features.clone()
+    }
+
+    pub(crate) fn task_name(&self, name: &str) -> String {
+        self.config.task_name(name)
+    }
+
+    pub(crate) fn supports_feature(&self, feature: Feature) -> bool {
+        self.session
+            .read()
+            .unwrap()
+            .server_features
+            .supports(feature)
+    }
+
+    fn require_feature(&self, feature: Feature) -> Result<(), Error> {
+        if self.supports_feature(feature) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedFeature(feature))
+        }
+    }
+
+    /// Returns the cached schema, reloading it if the server reported another schema version since.
+    pub async fn schema(&self) -> Result<Arc<Schema>, Error> {
+        if let Some(schema) = self.cached_schema() {
+            return Ok(schema);
+        }
+
+        let _loading = self.schema_load.lock().await;
+        if let Some(schema) = self.cached_schema() {
+            return Ok(schema);
+        }
+
+        let schema = loop {
+            let version = self.schema_version.load(Ordering::Relaxed);
+            let spaces: Vec<SpaceTuple> = self.select_all(consts::VSPACE_ID).await?;
+            let indexes: Vec<IndexTuple> = self.select_all(consts::VINDEX_ID).await?;
+
+            // retried if the schema was changed while it was being loaded
+            if self.schema_version.load(Ordering::Relaxed) == version {
+                break Arc::new(Schema::new(version, spaces, indexes));
+            }
+        };
+        *self.schema.lock().unwrap() = Some(schema.clone());
+
+        Ok(schema)
+    }
+
+    async fn select_all<T: DeserializeOwned>(&self, space_id: u32) -> Result<Vec<T>, Error> {
+        let resp: response::CallResponse<Vec<T>> = self
+            .make_request(|request_id| {
+                request::Select::new(
+                    request_id,
+                    SpaceRef::Id(space_id),
+                    IndexRef::Id(0),
+                    &[(); 0],
+                    IteratorType::All,
+                    u32::MAX,
+                    0,
+                )
+            })
+            .await?;
+        Ok(resp.into_data())
+    }
+
+    /// Looks the space name up in the schema unless the server accepts names.
+    async fn resolve_space<'a>(&self, space: SpaceRef<'a>) -> Result<SpaceRef<'a>, Error> {
+        match space {
+            SpaceRef::Name(name) if !self.supports_feature(Feature::SpaceAndIndexNames) => {
+                let schema = self.schema().await?;
+                let space = schema
+                    .space(name)
+                    .ok_or_else(|| Error::NoSuchSpace(space.to_string()))?;
+                Ok(SpaceRef::Id(space.id))
+            }
+            space => Ok(space),
+        }
+    }
+
+    /// Like [`Connection::resolve_space`], the index name is looked up in the space schema.
+    async fn resolve_index<'a>(
+        &self,
+        space: SpaceRef<'a>,
+        index: IndexRef<'a>,
+    ) -> Result<(SpaceRef<'a>, IndexRef<'a>), Error> {
+        let is_resolved = matches!((space, index), (SpaceRef::Id(_), IndexRef::Id(_)));
+        if is_resolved || self.supports_feature(Feature::SpaceAndIndexNames) {
+            return Ok((space, index));
+        }
+
+        let schema = self.schema().await?;
+        let space_schema = match space {
+            SpaceRef::Id(id) => schema.space_by_id(id),
+            SpaceRef::Name(name) => schema.space(name),
+        }
+        .ok_or_else(|| Error::NoSuchSpace(space.to_string()))?;
+        let index_id = match index {
+            IndexRef::Id(id) => id,
+            IndexRef::Name(name) => {
+                space_schema
+                    .index(name)
+                    .ok_or_else(|| Error::NoSuchIndex {
+                        space: space.to_string(),
+                        index: index.to_string(),
+                    })?
+                    .id
+            }
+        };
+
+        Ok((SpaceRef::Id(space_schema.id), IndexRef::Id(index_id)))
+    }
+
+    fn cached_schema(&self) -> Option<Arc<Schema>> {
+        let version = self.schema_version.load(Ordering::Relaxed);
+        self.schema
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|schema| schema.version() == version)
+            .cloned()
+    }
+
+    fn epoch(&self) -> u64 {
+        *self.epoch_tx.borrow()
+    }
 
-        // placeholder for body size (u32)
-        write_buf.extend_from_slice(&[0xCE, 0, 0, 0, 0]);
+    fn write_req_to_buf<R>(&self, req: &R) -> Result<PoolEntryGuard<Buffer>, Error>
+    where
+        R: Request<Buffer>,
+    {
+        let mut write_buf = self.buffer_pool.create().ok_or(Error::TooManyRequests)?;
+        let buffer_key = write_buf.key();
+        let result = encode_frame(req, write_buf.as_mut());
+        drop(write_buf);
 
-        req.encode(write_buf.as_mut())?;
+        #[cfg(test)]
+        self.write_buffer_keys.lock().unwrap().push(buffer_key);
 
-        let body_len = write_buf.len() as u32 - 5;
-        write_buf[1] = (body_len >> 24) as u8;
-        write_buf[2] = (body_len >> 16) as u8;
-        write_buf[3] = (body_len >> 8) as u8;
-        write_buf[4] = body_len as u8;
+        let buffer = PoolEntryGuard::new(buffer_key, self.buffer_pool.clone());
+        result.map(|()| buffer)
+    }
 
-        Ok(write_buf.key())
+    /// Sends a request the server does not reply to.
+    async fn send_oneway<Req>(&self, req: Req) -> Result<(), Error>
+    where
+        Req: Request<Buffer>,
+    {
+        let pending_write = PendingWrite {
+            buffer: self.write_req_to_buf(&req)?,
+            request_id: None,
+            epoch: self.epoch(),
+        };
+        if self
+            .requests_to_process_tx
+            .send(pending_write)
+            .await
+            .is_err()
+        {
+            return Err(self.await_err().await);
+        }
+        Ok(())
     }
 
-    async fn make_request_inner<Req, Resp, F>(&self, f: F) -> Result<Resp, Error>
+    async fn make_request_inner<Req, Resp, F>(
+        &self,
+        f: F,
+        push_tx: Option<PushSender>,
+        idempotent: bool,
+    ) -> Result<Resp, Error>
     where
         Req: Request<Buffer>,
         Resp: response::ResponseBody,
-        F: FnOnce(usize) -> Req,
+        F: Fn(usize) -> Req,
     {
+        let mut epoch_rx = self.epoch_tx.subscribe();
+        let _pending = CountGuard::new(&self.pending_count);
+
         let TarantoolResp {
             header:
                 response::ResponseHeader {
                     response_code_indicator,
                     ..
                 },
-            cursor_ref:
-                CursorRef {
-                    buffer_key,
-                    position,
-                },
-        } = {
+            cursor_ref: CursorRef { buffer, position },
+        } = loop {
+            let epoch = *epoch_rx.borrow_and_update();
+
             let (tx, rx) = oneshot::channel();
-            let request_id = {
-                let entry = self.pending_requests.vacant_entry().unwrap();
-                let request_id = entry.key();
-                entry.insert(RequestHandle { request_id, tx });
-                request_id
-            };
+            let request_id = self
+                .pending_requests
+                .insert(RequestHandle {
+                    tx,
+                    push_tx: push_tx.clone(),
+                })
+                .ok_or(Error::TooManyRequests)?;
 
             let _guard = SlabEntryGuard::new(request_id, &self.pending_requests);
 
             let req = f(request_id);
-            let buffer_key = self.write_req_to_buf(&req).unwrap();
-            self.requests_to_process_tx.send(buffer_key).await.unwrap();
+            let pending_write = PendingWrite {
+                buffer: self.write_req_to_buf(&req)?,
+                request_id: Some(request_id),
+                epoch,
+            };
+            if self
+                .requests_to_process_tx
+                .send(pending_write)
+                .await
+                .is_err()
+            {
+                return Err(self.await_err().await);
+            }
 
-            rx.await.unwrap()
+            tokio::select! {
+                biased;
+                resp = rx => match resp {
+                    Ok(resp) => break resp,
+                    Err(_) => return Err(Error::Disconnected),
+                },
+                _ = epoch_rx.changed() => {
+                    // the connection was lost, the request may or may not have been executed
+                    if !idempotent {
+                        return Err(Error::Disconnected);
+                    }
+                }
+            }
         };
 
-        let buffer = self.buffer_pool.get(buffer_key).unwrap();
+        let Some(buffer) = buffer.get() else {
+            return Err(Error::Disconnected);
+        };
         let mut cursor: Cursor<&Buffer> = Cursor::new(buffer.as_ref());
         cursor.set_position(position);
 
-        const IPROTO_OK: u32 = consts::IPROTO_OK as u32;
-        let result = match response_code_indicator {
-            IPROTO_OK => {
-                let data_resp = Resp::decode(&mut cursor).unwrap();
-                Ok(data_resp)
-            }
-            0x8000..=0x8fff => {
-                let _error_code = response_code_indicator - 0x8000;
-                let err_resp = response::ErrorResponse::decode(&mut cursor).unwrap();
-                Err(Error::TarantoolError(err_resp))
-            }
-            _ => {
-                panic!("error")
-            }
-        };
-        self.buffer_pool.clear(buffer_key);
-
+        let result = decode_response(Req::REQUEST_TYPE, response_code_indicator, &mut cursor);
+        if result
+            .as_ref()
+            .is_err_and(|err| err.tarantool_code() == Some(TarantoolErrorCode::WrongSchemaVersion))
+        {
+            *self.schema.lock().unwrap() = None;
+        }
         result
     }
 
@@ -227,9 +729,11 @@ impl Connection {
                 return err.clone();
             }
 
-            let result = error_rx.changed().await;
-            result.unwrap();
-            error_rx.borrow().clone().unwrap()
+            // the sender is dropped without an error only if the runtime shuts down
+            if error_rx.changed().await.is_err() {
+                return Error::Disconnected;
+            }
+            error_rx.borrow().clone().unwrap_or(Error::Disconnected)
         }
     }
 
@@ -237,149 +741,1615 @@ impl Connection {
     where
         Req: Request<Buffer>,
         Resp: response::ResponseBody,
-        F: FnOnce(usize) -> Req,
+        F: Fn(usize) -> Req,
+    {
+        self.make_request_with(&RequestOptions::default(), f).await
+    }
+
+    pub async fn make_request_with<Req, Resp, F>(
+        &self,
+        options: &RequestOptions,
+        f: F,
+    ) -> Result<Resp, Error>
+    where
+        Req: Request<Buffer>,
+        Resp: response::ResponseBody,
+        F: Fn(usize) -> Req,
     {
         use futures_lite::FutureExt;
-        self.make_request_inner(f)
-            .or(self.await_err().map(Err))
-            .await
+        let idempotent = options.idempotent.unwrap_or(Req::IDEMPOTENT);
+        let request = self
+            .make_request_inner(f, None, idempotent)
+            .or(self.await_err().map(Err));
+
+        let timeout = options.timeout.or(self.config.request_timeout);
+        with_timeout(timeout, request).await
+    }
+
+    /// Like [`Connection::make_request`], but forwards values pushed by the server
+    /// with `box.session.push` before the final response to `push_tx`.
+    async fn make_push_request<Req, Resp, F>(
+        &self,
+        f: F,
+        push_tx: PushSender,
+    ) -> Result<Resp, Error>
+    where
+        Req: Request<Buffer>,
+        Resp: response::ResponseBody,
+        F: Fn(usize) -> Req,
+    {
+        use futures_lite::FutureExt;
+        // pushed values cannot be taken back, so the request is never resent
+        let request = self
+            .make_request_inner(f, Some(push_tx), false)
+            .or(self.await_err().map(Err));
+
+        with_timeout(self.config.request_timeout, request).await
+    }
+
+    /// Sends IPROTO_PING and returns the round-trip time.
+    pub async fn ping(&self) -> Result<Duration, Error> {
+        let begin = Instant::now();
+        let _resp: response::EmptyResponse = self.make_request(request::Ping::new).await?;
+        Ok(begin.elapsed())
     }
 
     pub async fn call<T, R>(&self, name: &str, data: &T) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.call_with(name, data, &RequestOptions::default()).await
+    }
+
+    /// Like [`Connection::call`], e.g. to mark the procedure idempotent.
+    pub async fn call_with<T, R>(
+        &self,
+        name: &str,
+        data: &T,
+        options: &RequestOptions,
+    ) -> Result<R, Error>
     where
         T: Serialize,
         R: DeserializeOwned,
     {
         let resp: response::CallResponse<R> = self
-            .make_request(|request_id| request::Call::new(request_id, name, data))
+            .make_request_with(options, |request_id| {
+                request::Call::new(request_id, name, data)
+            })
             .await?;
         Ok(resp.into_data())
     }
 
-    pub async fn auth(&self, username: &str, password: Option<&str>) -> Result<(), Error> {
-        let _resp: response::EmptyResponse = self
-            .make_request(|request_id| {
-                request::Auth::new(request_id, &self.salt, username, password)
+    pub async fn eval<T, R>(&self, expr: &str, args: &T) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.eval_with(expr, args, &RequestOptions::default()).await
+    }
+
+    pub async fn eval_with<T, R>(
+        &self,
+        expr: &str,
+        args: &T,
+        options: &RequestOptions,
+    ) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let resp: response::CallResponse<R> = self
+            .make_request_with(options, |request_id| {
+                request::Eval::new(request_id, expr, args)
             })
             .await?;
-        Ok(())
+        Ok(resp.into_data())
     }
 
-    async fn writer(
+    /// Calls a stored procedure that reports progress with `box.session.push`.
+    /// Returns the stream of pushed values, which ends once the call completes,
+    /// and the future of the final result.
+    ///
+    /// The call is driven by whichever half is polled, so the stream may be drained
+    /// before the result is awaited. Dropping both halves cancels the call.
+    pub fn call_streaming<'a, T, R>(
+        &'a self,
+        name: &'a str,
+        data: &'a T,
+    ) -> (
+        impl Stream<Item = rmpv::Value> + Unpin + 'a,
+        impl Future<Output = Result<R, Error>> + 'a,
+    )
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let (push_tx, push_rx) = futures::channel::mpsc::unbounded();
+        let request = async move {
+            let resp: response::CallResponse<R> = self
+                .make_push_request(
+                    |request_id| request::Call::new(request_id, name, data),
+                    push_tx,
+                )
+                .await?;
+            Ok(resp.into_data())
+        };
+
+        let call = Arc::new(Mutex::new(StreamingCall {
+            request: Some(Box::pin(request)),
+            output: None,
+            wakers: [None, None],
+        }));
+        let pushes = PushStream {
+            call: call.clone(),
+            push_rx,
+        };
+        (pushes, StreamingResult { call })
+    }
+
+    pub async fn select<K, T>(
         &self,
-        mut requests_to_process_rx: mpsc::Receiver<usize>,
-        write_stream: OwnedWriteHalf,
-    ) -> std::io::Result<()> {
-        use tokio::io::AsyncWriteExt;
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+        iterator: IteratorType,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<T>, Error>
+    where
+        K: Serialize,
+        T: DeserializeOwned,
+    {
+        let (space, index) = self.resolve_index(space.into(), index.into()).await?;
+        let resp: response::CallResponse<Vec<T>> = self
+            .make_request(|request_id| {
+                request::Select::new(request_id, space, index, key, iterator, limit, offset)
+            })
+            .await?;
+        Ok(resp.into_data())
+    }
 
-        let mut write_stream = BufWriter::with_capacity(WRITE_BUFFER, write_stream);
+    /// Selects up to `limit` tuples following `after`, or from the start if it is `None`,
+    /// together with the position to request the next page from.
+    pub async fn select_page<K, T>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+        iterator: IteratorType,
+        limit: u32,
+        after: Option<After<'_>>,
+    ) -> Result<SelectResponse<T>, Error>
+    where
+        K: Serialize,
+        T: DeserializeOwned,
+    {
+        self.require_feature(Feature::Pagination)?;
+        let (space, index) = self.resolve_index(space.into(), index.into()).await?;
+        self.make_request(|request_id| {
+            request::Select::new(request_id, space, index, key, iterator, limit, 0)
+                .after(after)
+                .fetch_position(true)
+        })
+        .await
+    }
 
-        while self.state.load(Ordering::Relaxed) == CONNECTED_STATE {
-            let buffer_key = requests_to_process_rx.recv().await.unwrap();
-            {
-                let write_buf = self.buffer_pool.clone().get_owned(buffer_key).unwrap();
-                write_stream.write_all(&write_buf).await?;
-                self.buffer_pool.clear(buffer_key);
-            }
-
-            // TODO: change batching behaviour
-            const OPTIMAL_PAYLOAD_SIZE: usize = 1000;
-            while write_stream.buffer().len() < OPTIMAL_PAYLOAD_SIZE {
-                if let Ok(buffer_key) = requests_to_process_rx.try_recv() {
-                    let write_buf = self.buffer_pool.clone().get_owned(buffer_key).unwrap();
-                    write_stream.write_all(&write_buf).await?;
-                    self.buffer_pool.clear(buffer_key);
-                } else {
-                    break;
+    /// Streams all tuples matching `key` with the `EQ` iterator, an empty key scans the whole index.
+    /// Tuples are fetched in pages of `page_size`, each continuing after the last one.
+    pub fn scan<'a, K, T>(
+        &'a self,
+        space: impl Into<SpaceRef<'a>>,
+        index: impl Into<IndexRef<'a>>,
+        key: &'a K,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<T, Error>> + 'a
+    where
+        K: Serialize + Sync,
+        T: DeserializeOwned + 'a,
+    {
+        let (space, index) = (space.into(), index.into());
+
+        // `None` once the last page is fetched
+        let first_page: Option<Option<Position>> = Some(None);
+        futures::stream::unfold(first_page, move |state| async move {
+            let position = state?;
+            let after = position.as_ref().map(After::Position);
+            let page = self
+                .select_page(space, index, key, IteratorType::Eq, page_size, after)
+                .await;
+
+            match page {
+                Ok(SelectResponse { tuples, position }) => {
+                    let next = position.filter(|_| tuples.len() >= page_size as usize);
+                    Some((Ok(tuples), next.map(Some)))
                 }
+                Err(err) => Some((Err(err), None)),
             }
-
-            write_stream.flush().await?;
-        }
-
-        Ok(())
+        })
+        .map_ok(|tuples: Vec<T>| futures::stream::iter(tuples.into_iter().map(Ok)))
+        .try_flatten()
     }
 
-    async fn reader(&self, read_stream: OwnedReadHalf) -> std::io::Result<()> {
-        use tokio::io::AsyncReadExt;
-
-        let mut read_stream = BufReader::with_capacity(READ_BUFFER, read_stream);
-
-        let mut payload_len_raw = [0; 5];
-        while self.state.load(Ordering::Relaxed) == CONNECTED_STATE {
+    pub async fn insert<T, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        tuple: &T,
+    ) -> Result<Option<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let space = self.resolve_space(space.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Insert::new(request_id, space, tuple))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn replace<T, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        tuple: &T,
+    ) -> Result<Option<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let space = self.resolve_space(space.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Replace::new(request_id, space, tuple))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn delete<K, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+    ) -> Result<Option<R>, Error>
+    where
+        K: Serialize,
+        R: DeserializeOwned,
+    {
+        let (space, index) = self.resolve_index(space.into(), index.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Delete::new(request_id, space, index, key))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn update<K, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+        ops: &UpdateOps<'_>,
+    ) -> Result<Option<R>, Error>
+    where
+        K: Serialize,
+        R: DeserializeOwned,
+    {
+        let (space, index) = self.resolve_index(space.into(), index.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Update::new(request_id, space, index, key, ops))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn upsert<T>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        tuple: &T,
+        ops: &UpdateOps<'_>,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let space = self.resolve_space(space.into()).await?;
+        let _resp: response::EmptyResponse = self
+            .make_request(|request_id| request::Upsert::new(request_id, space, tuple, ops))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn execute<T, R>(&self, sql: &str, binds: &T) -> Result<SqlResponse<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.execute_query(request::SqlQuery::Text(sql), binds)
+            .await
+    }
+
+    async fn execute_query<T, R>(
+        &self,
+        query: request::SqlQuery<'_>,
+        binds: &T,
+    ) -> Result<SqlResponse<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.make_request(|request_id| request::Execute::new(request_id, query, binds))
+            .await
+    }
+
+    pub async fn prepare(self: &Arc<Self>, sql: &str) -> Result<PreparedStatement, Error> {
+        let resp: response::PrepareResponse = self
+            .make_request(|request_id| {
+                request::Prepare::new(request_id, request::SqlQuery::Text(sql))
+            })
+            .await?;
+
+        Ok(PreparedStatement {
+            conn: self.clone(),
+            stmt_id: resp.stmt_id,
+            bind_count: resp.bind_count,
+            bind_metadata: resp.bind_metadata,
+            metadata: resp.metadata,
+        })
+    }
+
+    async fn unprepare(&self, stmt_id: u64) -> Result<(), Error> {
+        let _resp: response::EmptyResponse = self
+            .make_request(|request_id| {
+                request::Prepare::new(request_id, request::SqlQuery::Statement(stmt_id))
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Begins an interactive transaction in a new stream.
+    pub async fn begin(
+        self: &Arc<Self>,
+        isolation: TxnIsolation,
+        timeout: Option<Duration>,
+    ) -> Result<Transaction, Error> {
+        self.require_feature(Feature::Streams)?;
+        self.require_feature(Feature::Transactions)?;
+
+        let mut txn = Transaction {
+            conn: self.clone(),
+            stream_id: self.next_stream_id.fetch_add(1, Ordering::Relaxed),
+            epoch: self.epoch(),
+            finished: false,
+        };
+
+        let result: Result<response::EmptyResponse, Error> = txn
+            .make_request(|request_id| request::Begin::new(request_id, isolation, timeout))
+            .await;
+        if let Err(err) = result {
+            txn.finished = true;
+            return Err(err);
+        }
+
+        Ok(txn)
+    }
+
+    /// Subscribes to a `box.broadcast` key. The stream yields the current value
+    /// of the key and then every update; the key is unwatched when all streams
+    /// subscribed to it are dropped.
+    pub async fn watch(self: &Arc<Self>, key: &str) -> Result<WatchStream, Error> {
+        self.require_feature(Feature::Watchers)?;
+
+        let (rx, is_new) = {
+            let mut watchers = self.watchers.lock().unwrap();
+            match watchers.get(key) {
+                Some(tx) => {
+                    let mut rx = tx.subscribe();
+                    rx.mark_changed();
+                    (rx, false)
+                }
+                None => {
+                    let (tx, rx) = watch::channel(None);
+                    watchers.insert(key.to_owned(), tx);
+                    (rx, true)
+                }
+            }
+        };
+
+        let stream = WatchStream::new(self.clone(), key.to_owned(), rx);
+        if is_new {
+            self.send_oneway(request::Watch::new(key)).await?;
+        }
+
+        Ok(stream)
+    }
+
+    /// Unwatches a key the last stream was dropped for. The key stays registered until
+    /// the Unwatch is queued, so a concurrent [`Connection::watch`] subscribes to it
+    /// instead of sending its Watch ahead of the Unwatch, and the key is watched again.
+    async fn unwatch(&self, key: &str) {
+        let _ = self.send_oneway(request::Unwatch::new(key)).await;
+
+        let rewatch = {
+            let mut watchers = self.watchers.lock().unwrap();
+            match watchers.get(key) {
+                Some(tx) if tx.receiver_count() == 0 => {
+                    watchers.remove(key);
+                    false
+                }
+                Some(_) => true,
+                None => false,
+            }
+        };
+        if rewatch {
+            let _ = self.send_oneway(request::Watch::new(key)).await;
+        }
+    }
+
+    fn handle_event(&self, event: response::Event) -> Result<(), Error> {
+        let is_watched = match self.watchers.lock().unwrap().get(&event.key) {
+            Some(tx) => {
+                tx.send_replace(Some(event.data));
+                // the key is being unwatched
+                tx.receiver_count() > 0
+            }
+            None => false,
+        };
+
+        // the server sends the next event only after the previous one is acknowledged
+        if !is_watched {
+            return Ok(());
+        }
+
+        // the reader must not wait for the channel, the writer may be waiting for responses
+        let pending_write = PendingWrite {
+            buffer: self.write_req_to_buf(&request::Watch::new(&event.key))?,
+            request_id: None,
+            epoch: self.epoch(),
+        };
+        match self.requests_to_process_tx.try_send(pending_write) {
+            Ok(()) | Err(mpsc::error::TrySendError::Closed(_)) => {}
+            Err(mpsc::error::TrySendError::Full(pending_write)) => {
+                let requests_to_process_tx = self.requests_to_process_tx.clone();
+                tokio::spawn(async move {
+                    let _ = requests_to_process_tx.send(pending_write).await;
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Authenticates with the method the server advertises, `chap-sha1` if it advertises none.
+    /// An advertised `pap-sha256` is accepted over TLS only, use [`Connection::auth_with`]
+    /// to send the password over a plain connection anyway.
+    pub async fn auth(&self, username: &str, password: Option<&str>) -> Result<(), Error> {
+        self.authenticate(username, password, None).await
+    }
+
+    pub async fn auth_with(
+        &self,
+        username: &str,
+        password: Option<&str>,
+        method: AuthMethod,
+    ) -> Result<(), Error> {
+        self.authenticate(username, password, Some(method)).await
+    }
+
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: Option<&str>,
+        method: Option<AuthMethod>,
+    ) -> Result<(), Error> {
+        let (salt, auth_method) = self.auth_params(method)?;
+        let _resp: response::EmptyResponse = self
+            .make_request(|request_id| {
+                request::Auth::new(request_id, auth_method, &salt, username, password)
+            })
+            .await?;
+
+        // kept to authenticate again after reconnect, the advertised method may change meanwhile
+        *self.credentials.lock().unwrap() = Some(Credentials {
+            username: username.to_owned(),
+            password: password.map(str::to_owned),
+            method,
+        });
+        Ok(())
+    }
+
+    /// Salt of the current connection and the method to authenticate with: the requested one,
+    /// otherwise the one the server advertises. An advertised `pap-sha256` is refused unless
+    /// the connection is encrypted, the server cannot be trusted to ask for the plain password.
+    fn auth_params(&self, method: Option<AuthMethod>) -> Result<(Vec<u8>, AuthMethod), Error> {
+        let session = self.session.read().unwrap();
+        let auth_method = match (method, &session.server_features.auth_type) {
+            (Some(method), _) => method,
+            (None, None) => AuthMethod::default(),
+            (None, Some(name)) => match AuthMethod::from_name(name) {
+                Some(AuthMethod::PapSha256) if !self.is_encrypted() => {
+                    return Err(Error::InsecureAuthMethod(AuthMethod::PapSha256));
+                }
+                Some(method) => method,
+                None => return Err(Error::UnsupportedAuthMethod(name.clone())),
+            },
+        };
+        Ok((session.salt.clone(), auth_method))
+    }
+
+    fn is_encrypted(&self) -> bool {
+        #[cfg(feature = "tls")]
+        if self.config.tls.is_some() {
+            return true;
+        }
+        false
+    }
+
+    async fn writer(
+        &self,
+        requests_to_process_rx: &mut mpsc::Receiver<PendingWrite>,
+        write_stream: WriteHalf,
+    ) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut write_stream =
+            BufWriter::with_capacity(self.config.write_buffer_size, write_stream);
+        let epoch = self.epoch();
+
+        while self.state.load(Ordering::Relaxed) == CONNECTED_STATE {
+            let Some(pending_write) = requests_to_process_rx.recv().await else {
+                return Ok(());
+            };
+            self.write_pending(&mut write_stream, pending_write, epoch)
+                .await?;
+
+            while write_stream.buffer().len() < self.config.batch_size {
+                if let Ok(pending_write) = requests_to_process_rx.try_recv() {
+                    self.write_pending(&mut write_stream, pending_write, epoch)
+                        .await?;
+                } else {
+                    break;
+                }
+            }
+
+            write_stream.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the request unless it was encoded for a connection that is already lost.
+    async fn write_pending(
+        &self,
+        write_stream: &mut BufWriter<WriteHalf>,
+        pending_write: PendingWrite,
+        epoch: u64,
+    ) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let PendingWrite {
+            buffer,
+            request_id,
+            epoch: request_epoch,
+        } = pending_write;
+
+        let is_cancelled = request_id.is_some_and(|id| !self.pending_requests.contains(id));
+        if request_epoch == epoch
+            && !is_cancelled
+            && let Some(write_buf) = buffer.get_owned()
+        {
+            write_stream.write_all(&write_buf).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn reader(&self, read_stream: ReadHalf) -> std::io::Result<()> {
+        use tokio::io::AsyncReadExt;
+
+        let mut read_stream = BufReader::with_capacity(self.config.read_buffer_size, read_stream);
+
+        let mut payload_len_raw = [0; 5];
+        while self.state.load(Ordering::Relaxed) == CONNECTED_STATE {
             read_stream.read_exact(&mut payload_len_raw).await?;
+            let len = frame_len(&payload_len_raw, self.config.max_frame_size)?;
+
+            let mut resp_buf = self
+                .buffer_pool
+                .clone()
+                .create_owned()
+                .ok_or_else(|| std::io::Error::other("buffer pool exhausted"))?;
+            let buffer = PoolEntryGuard::new(resp_buf.key(), self.buffer_pool.clone());
+            resp_buf.resize(len, 0);
+            read_stream.read_exact(&mut resp_buf).await?;
+
+            let resp_buf_ref: &mut Buffer = resp_buf.as_mut();
+            let mut resp_reader = Cursor::new(resp_buf_ref);
+
+            let header = response::ResponseHeader::decode(&mut resp_reader)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+            let request_id = header.request_id();
+            if let Some(schema_version) = header.schema_version {
+                self.schema_version.store(schema_version, Ordering::Relaxed);
+            }
+
+            if header.response_code_indicator() == consts::IPROTO_EVENT as u32 {
+                let event = response::Event::decode(&mut resp_reader);
+                drop(resp_buf);
+
+                // malformed events are skipped, the frame boundaries are still known
+                if let Ok(event) = event {
+                    let _ = self.handle_event(event);
+                }
+                continue;
+            }
+
+            if header.response_code_indicator() == consts::IPROTO_CHUNK as u32 {
+                if let Some(req) = self.pending_requests.get(request_id)
+                    && let Some(push_tx) = &req.push_tx
+                    && let Ok(pushed) =
+                        response::CallResponse::<Vec<rmpv::Value>>::decode(&mut resp_reader)
+                {
+                    for value in pushed.into_data() {
+                        let _ = push_tx.unbounded_send(value);
+                    }
+                }
+                continue;
+            }
+
+            let position = resp_reader.position();
+
+            // resp_buf must be dropped before it was sent to prevent mutual access by receiver
+            // (if receivers gets the key before it was dropped it receives null)
+            drop(resp_buf);
+
+            let result = TarantoolResp {
+                header,
+                cursor_ref: CursorRef { buffer, position },
+            };
 
-            if payload_len_raw[0] != 0xCE {
-                panic!("invalid resp");
+            // late responses of cancelled or timed out requests are dropped
+            // along with their buffers
+            if let Some(req) = self.pending_requests.take(request_id) {
+                let _ = req.tx.send(result);
             }
+        }
+
+        Ok(())
+    }
+}
+
+/// Connects to the first reachable address and reads the server greeting.
+async fn dial(endpoint: &Endpoint, config: &Config) -> std::io::Result<(Socket, Greeting)> {
+    let addrs = match endpoint {
+        Endpoint::Tcp(addrs) => addrs,
+        Endpoint::Unix(path) => {
+            let connect = async { Ok(Socket::Unix(UnixStream::connect(path).await?)) };
+            return handshake(connect, config).await;
+        }
+    };
+
+    let mut last_err = None;
+    for addr in addrs {
+        match handshake(connect_tcp(addr, config), config).await {
+            Ok(connected) => return Ok(connected),
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "could not resolve to any address",
+        )
+    }))
+}
+
+/// Opens the stream and reads the greeting within the connect timeout.
+async fn handshake(
+    connect: impl Future<Output = std::io::Result<Socket>>,
+    config: &Config,
+) -> std::io::Result<(Socket, Greeting)> {
+    use tokio::io::AsyncReadExt;
+
+    let handshake = async {
+        let mut stream = connect.await?;
+        let mut greeting_raw = [0; GREETING_SIZE];
+        stream.read_exact(&mut greeting_raw).await?;
+        let greeting = Greeting::decode(&greeting_raw)?;
+        Ok((stream, greeting))
+    };
+
+    match config.connect_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, handshake)
+            .await
+            .unwrap_or_else(|_| Err(std::io::ErrorKind::TimedOut.into())),
+        None => handshake.await,
+    }
+}
+
+async fn connect_tcp(addr: &SocketAddr, config: &Config) -> std::io::Result<Socket> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(config.tcp_nodelay)?;
+    if let Some(idle) = config.tcp_keepalive {
+        socket::setsockopt(&stream, socket::sockopt::KeepAlive, &true)?;
+        socket::setsockopt(
+            &stream,
+            socket::sockopt::TcpKeepIdle,
+            &(idle.as_secs().max(1) as u32),
+        )?;
+    }
+
+    #[cfg(feature = "tls")]
+    if let Some(tls) = &config.tls {
+        return Ok(Socket::Tls(Box::new(tls.connect(stream).await?)));
+    }
+
+    Ok(Socket::Tcp(stream))
+}
+
+/// Appends the request prefixed with its MP_UINT32 encoded length.
+fn encode_frame<R: Request<Buffer>>(req: &R, buf: &mut Buffer) -> Result<(), Error> {
+    let start = buf.len();
+
+    // placeholder for body size (u32)
+    buf.extend_from_slice(&[0xCE, 0, 0, 0, 0]);
+
+    req.encode(buf).map_err(|err| Error::InvalidEncoding {
+        request_type: R::REQUEST_TYPE,
+        source: Arc::new(err),
+    })?;
+
+    let body_len = (buf.len() - start - 5) as u32;
+    buf[start + 1..start + 5].copy_from_slice(&body_len.to_be_bytes());
+
+    Ok(())
+}
+
+/// Decodes the MP_UINT32 length prefix of a response.
+/// The stream cannot be resynchronized after a broken prefix.
+fn frame_len(raw: &[u8; 5], max_frame_size: usize) -> std::io::Result<usize> {
+    if raw[0] != 0xCE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid frame length marker {:#04x}", raw[0]),
+        ));
+    }
+
+    let len = u32::from_be_bytes([raw[1], raw[2], raw[3], raw[4]]) as usize;
+    if len > max_frame_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds the limit of {max_frame_size} bytes"),
+        ));
+    }
+    Ok(len)
+}
+
+fn decode_response<Resp: ResponseBody>(
+    request_type: u8,
+    code: u32,
+    reader: &mut impl std::io::Read,
+) -> Result<Resp, Error> {
+    let invalid_decoding = |err| Error::InvalidDecoding {
+        request_type,
+        source: Arc::new(err),
+    };
+
+    const IPROTO_OK: u32 = consts::IPROTO_OK as u32;
+    match code {
+        IPROTO_OK => Resp::decode(reader).map_err(invalid_decoding),
+        0x8000..=0x8fff => {
+            let mut err_resp = ErrorResponse::decode(reader).map_err(invalid_decoding)?;
+            err_resp.code = TarantoolErrorCode::from_code(code - 0x8000);
+            Err(Error::TarantoolError(Box::new(err_resp)))
+        }
+        code => Err(Error::InvalidResponse { request_type, code }),
+    }
+}
+
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    request: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, request)
+            .await
+            .unwrap_or(Err(Error::Timeout)),
+        None => request.await,
+    }
+}
+
+fn task_error(result: Result<std::io::Result<()>, tokio::task::JoinError>) -> std::io::Error {
+    match result {
+        Ok(Ok(())) => std::io::ErrorKind::ConnectionAborted.into(),
+        Ok(Err(err)) => err,
+        Err(err) => std::io::Error::other(err),
+    }
+}
+
+/// Server-side prepared SQL statement, unprepared when dropped.
+pub struct PreparedStatement {
+    conn: Arc<Connection>,
+    stmt_id: u64,
+    bind_count: u64,
+    bind_metadata: Vec<ColumnMetadata>,
+    metadata: Vec<ColumnMetadata>,
+}
+
+impl PreparedStatement {
+    pub fn stmt_id(&self) -> u64 {
+        self.stmt_id
+    }
+
+    pub fn bind_count(&self) -> u64 {
+        self.bind_count
+    }
+
+    pub fn bind_metadata(&self) -> &[ColumnMetadata] {
+        &self.bind_metadata
+    }
+
+    pub fn metadata(&self) -> &[ColumnMetadata] {
+        &self.metadata
+    }
+
+    pub async fn execute<T, R>(&self, binds: &T) -> Result<SqlResponse<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.conn
+            .execute_query(request::SqlQuery::Statement(self.stmt_id), binds)
+            .await
+    }
+}
+
+impl Drop for PreparedStatement {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let conn = self.conn.clone();
+        let stmt_id = self.stmt_id;
+        runtime.spawn(async move {
+            let _ = conn.unprepare(stmt_id).await;
+        });
+    }
+}
+
+/// Interactive transaction bound to a stream.
+/// Rolled back when dropped without [`Transaction::commit`].
+pub struct Transaction {
+    conn: Arc<Connection>,
+    stream_id: u64,
+
+    /// the server rolls back transactions of closed connections,
+    /// so the transaction is unusable after a reconnect
+    epoch: u64,
+    finished: bool,
+}
+
+impl Transaction {
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+
+    async fn make_request<Req, Resp, F>(&self, f: F) -> Result<Resp, Error>
+    where
+        Req: Request<Buffer>,
+        Resp: response::ResponseBody,
+        F: Fn(usize) -> Req,
+    {
+        if self.conn.epoch() != self.epoch {
+            return Err(Error::Disconnected);
+        }
+        self.conn
+            .make_request(|request_id| request::Streamed::new(self.stream_id, f(request_id)))
+            .await
+    }
+
+    pub async fn commit(mut self) -> Result<(), Error> {
+        let result: Result<response::EmptyResponse, Error> =
+            self.make_request(request::Commit::new).await;
+        self.finished = true;
+        result.map(|_| ())
+    }
+
+    pub async fn rollback(mut self) -> Result<(), Error> {
+        let result: Result<response::EmptyResponse, Error> =
+            self.make_request(request::Rollback::new).await;
+        self.finished = true;
+        result.map(|_| ())
+    }
+
+    pub async fn select<K, T>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+        iterator: IteratorType,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<T>, Error>
+    where
+        K: Serialize,
+        T: DeserializeOwned,
+    {
+        let (space, index) = self.conn.resolve_index(space.into(), index.into()).await?;
+        let resp: response::CallResponse<Vec<T>> = self
+            .make_request(|request_id| {
+                request::Select::new(request_id, space, index, key, iterator, limit, offset)
+            })
+            .await?;
+        Ok(resp.into_data())
+    }
+
+    pub async fn insert<T, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        tuple: &T,
+    ) -> Result<Option<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let space = self.conn.resolve_space(space.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Insert::new(request_id, space, tuple))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn replace<T, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        tuple: &T,
+    ) -> Result<Option<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let space = self.conn.resolve_space(space.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Replace::new(request_id, space, tuple))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn delete<K, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+    ) -> Result<Option<R>, Error>
+    where
+        K: Serialize,
+        R: DeserializeOwned,
+    {
+        let (space, index) = self.conn.resolve_index(space.into(), index.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Delete::new(request_id, space, index, key))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn update<K, R>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        index: impl Into<IndexRef<'_>>,
+        key: &K,
+        ops: &UpdateOps<'_>,
+    ) -> Result<Option<R>, Error>
+    where
+        K: Serialize,
+        R: DeserializeOwned,
+    {
+        let (space, index) = self.conn.resolve_index(space.into(), index.into()).await?;
+        let resp: response::CallResponse<Vec<R>> = self
+            .make_request(|request_id| request::Update::new(request_id, space, index, key, ops))
+            .await?;
+        Ok(resp.into_data().into_iter().next())
+    }
+
+    pub async fn upsert<T>(
+        &self,
+        space: impl Into<SpaceRef<'_>>,
+        tuple: &T,
+        ops: &UpdateOps<'_>,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let space = self.conn.resolve_space(space.into()).await?;
+        let _resp: response::EmptyResponse = self
+            .make_request(|request_id| request::Upsert::new(request_id, space, tuple, ops))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn call<T, R>(&self, name: &str, data: &T) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let resp: response::CallResponse<R> = self
+            .make_request(|request_id| request::Call::new(request_id, name, data))
+            .await?;
+        Ok(resp.into_data())
+    }
+
+    pub async fn eval<T, R>(&self, expr: &str, args: &T) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let resp: response::CallResponse<R> = self
+            .make_request(|request_id| request::Eval::new(request_id, expr, args))
+            .await?;
+        Ok(resp.into_data())
+    }
+
+    pub async fn execute<T, R>(&self, sql: &str, binds: &T) -> Result<SqlResponse<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.make_request(|request_id| {
+            request::Execute::new(request_id, request::SqlQuery::Text(sql), binds)
+        })
+        .await
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if self.finished || self.conn.epoch() != self.epoch {
+            return;
+        }
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let conn = self.conn.clone();
+        let stream_id = self.stream_id;
+        runtime.spawn(async move {
+            let _: Result<response::EmptyResponse, Error> = conn
+                .make_request(|request_id| {
+                    request::Streamed::new(stream_id, request::Rollback::new(request_id))
+                })
+                .await;
+        });
+    }
+}
+
+const PUSH_STREAM: usize = 0;
+const STREAMING_RESULT: usize = 1;
+
+/// State shared by the halves returned by [`Connection::call_streaming`].
+struct StreamingCall<F: Future> {
+    request: Option<Pin<Box<F>>>,
+    output: Option<F::Output>,
+    /// the last wakers of [`PushStream`] and [`StreamingResult`]
+    wakers: [Option<Waker>; 2],
+}
+
+impl<F: Future> StreamingCall<F> {
+    /// Polls the request on behalf of `half`.
+    fn drive(&mut self, half: usize, cx: &mut Context<'_>) {
+        self.wakers[half] = Some(cx.waker().clone());
+        if let Some(request) = &mut self.request
+            && let Poll::Ready(output) = request.as_mut().poll(cx)
+        {
+            self.request = None;
+            self.output = Some(output);
+            self.wake(1 - half);
+        }
+    }
+
+    fn wake(&mut self, half: usize) {
+        if let Some(waker) = self.wakers[half].take() {
+            waker.wake();
+        }
+    }
+}
+
+struct PushStream<F: Future> {
+    call: Arc<Mutex<StreamingCall<F>>>,
+    push_rx: futures::channel::mpsc::UnboundedReceiver<rmpv::Value>,
+}
+
+impl<F: Future> Stream for PushStream<F> {
+    type Item = rmpv::Value;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.call.lock().unwrap().drive(PUSH_STREAM, cx);
+        // the sender is dropped with the request, so the stream ends after the final response
+        self.push_rx.poll_next_unpin(cx)
+    }
+}
+
+impl<F: Future> Drop for PushStream<F> {
+    fn drop(&mut self) {
+        // the result may be waiting on a request this stream was the last to poll
+        let mut call = self.call.lock().unwrap();
+        call.wakers[PUSH_STREAM] = None;
+        call.wake(STREAMING_RESULT);
+    }
+}
+
+struct StreamingResult<F: Future> {
+    call: Arc<Mutex<StreamingCall<F>>>,
+}
+
+impl<F: Future> Future for StreamingResult<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut call = self.call.lock().unwrap();
+        call.drive(STREAMING_RESULT, cx);
+        match call.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<F: Future> Drop for StreamingResult<F> {
+    fn drop(&mut self) {
+        let mut call = self.call.lock().unwrap();
+        call.wakers[STREAMING_RESULT] = None;
+        call.wake(PUSH_STREAM);
+    }
+}
+
+/// Stream of `box.broadcast` values of a watched key.
+pub struct WatchStream {
+    conn: Arc<Connection>,
+    key: String,
+    inner: BoxStream<'static, rmpv::Value>,
+}
+
+impl WatchStream {
+    fn new(conn: Arc<Connection>, key: String, rx: watch::Receiver<Option<rmpv::Value>>) -> Self {
+        let inner = futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                rx.changed().await.ok()?;
+                let value = rx.borrow_and_update().clone();
+                if let Some(value) = value {
+                    return Some((value, rx));
+                }
+            }
+        });
+
+        Self {
+            conn,
+            key,
+            inner: inner.boxed(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Stream for WatchStream {
+    type Item = rmpv::Value;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for WatchStream {
+    fn drop(&mut self) {
+        // drops the receiver of this stream
+        self.inner = futures::stream::empty().boxed();
+
+        let is_last = self
+            .conn
+            .watchers
+            .lock()
+            .unwrap()
+            .get(&self.key)
+            .is_some_and(|tx| tx.receiver_count() == 0);
+        if !is_last {
+            return;
+        }
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            self.conn.watchers.lock().unwrap().remove(&self.key);
+            return;
+        };
+
+        let conn = self.conn.clone();
+        let key = std::mem::take(&mut self.key);
+        runtime.spawn(async move { conn.unwatch(&key).await });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        After, AuthMethod, Connection, Error, Feature, IteratorType, ReconnectPolicy,
+        RequestOptions, SelectResponse, SqlResponse, TarantoolErrorCode, TxnIsolation, UpdateOps,
+    };
+    use crate::iproto::consts;
+    use crate::testing::{self, MockServer};
+    use futures::TryStreamExt;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    const TESTING_HOST: &str = "localhost:3301";
+
+    async fn conn() -> Arc<Connection> {
+        Connection::connect(TESTING_HOST).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn client_test() {
+        let conn = conn().await;
+        let t = Duration::from_secs(2);
+
+        timeout(t, conn.auth("guest", None)).await.unwrap().unwrap();
+
+        let (result,): (usize,) = timeout(t, conn.call("sum", &(1, 2)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, 3);
+
+        let (result,): (usize,) = timeout(t, conn.call("sum", &(1, 2)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, 3);
+    }
+
+    #[tokio::test]
+    async fn select_test() {
+        let conn = conn().await;
+
+        let tuples: Vec<(u32, String)> = conn
+            .select(512, 0, &(2,), IteratorType::Ge, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(tuples, vec![(2, "two".into()), (3, "three".into())]);
+
+        let tuples: Vec<(u32, String)> = conn
+            .select(512, 0, &[(); 0], IteratorType::All, 1, 1)
+            .await
+            .unwrap();
+        assert_eq!(tuples, vec![(2, "two".into())]);
+    }
+
+    #[tokio::test]
+    async fn schema_test() {
+        let conn = conn().await;
+
+        let schema = conn.schema().await.unwrap();
+        let space = schema.space_by_id(512).unwrap();
+        assert_eq!(schema.space(&space.name).unwrap().id, 512);
+        assert_eq!(space.indexes[0].id, 0);
+        assert!(space.indexes[0].unique);
+
+        assert!(Arc::ptr_eq(&schema, &conn.schema().await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn insert_replace_delete_test() {
+        let conn = conn().await;
+        let _: Option<(u32, String)> = conn.delete(512, 0, &(100,)).await.unwrap();
+
+        let inserted: Option<(u32, String)> = conn.insert(512, &(100, "hundred")).await.unwrap();
+        assert_eq!(inserted, Some((100, "hundred".into())));
+
+        let replaced: Option<(u32, String)> =
+            conn.replace(512, &(100, "one hundred")).await.unwrap();
+        assert_eq!(replaced, Some((100, "one hundred".into())));
+
+        let deleted: Option<(u32, String)> = conn.delete(512, 0, &(100,)).await.unwrap();
+        assert_eq!(deleted, Some((100, "one hundred".into())));
+
+        let deleted: Option<(u32, String)> = conn.delete(512, 0, &(100,)).await.unwrap();
+        assert_eq!(deleted, None);
+    }
+
+    #[tokio::test]
+    async fn update_upsert_test() {
+        let conn = conn().await;
+        let _: Option<(u32, String, u64)> = conn.delete(512, 0, &(200,)).await.unwrap();
+
+        let ops = UpdateOps::new().add(2, 5);
+        conn.upsert(512, &(200, "counter", 1), &ops).await.unwrap();
+        conn.upsert(512, &(200, "counter", 1), &ops).await.unwrap();
+
+        let ops = UpdateOps::new().sub(2, 2).splice(1, 1, 1, "C");
+        let updated: Option<(u32, String, u64)> = conn.update(512, 0, &(200,), &ops).await.unwrap();
+        assert_eq!(updated, Some((200, "Counter".into(), 4)));
 
-            let len = ((payload_len_raw[1] as usize) << 24)
-                + ((payload_len_raw[2] as usize) << 16)
-                + ((payload_len_raw[3] as usize) << 8)
-                + (payload_len_raw[4] as usize);
+        let missing: Option<(u32, String, u64)> = conn.update(512, 0, &(201,), &ops).await.unwrap();
+        assert_eq!(missing, None);
 
-            let mut resp_buf = self.buffer_pool.clone().create_owned().unwrap();
-            let buffer_key = resp_buf.key();
-            resp_buf.resize(len, 0);
-            read_stream.read_exact(&mut resp_buf).await?;
+        let _: Option<(u32, String, u64)> = conn.delete(512, 0, &(200,)).await.unwrap();
+    }
 
-            let resp_buf_ref: &mut Buffer = resp_buf.as_mut();
-            let mut resp_reader = Cursor::new(resp_buf_ref);
+    #[tokio::test]
+    async fn execute_test() {
+        let conn = conn().await;
 
-            let header = response::ResponseHeader::decode(&mut resp_reader).unwrap();
-            let request_id = header.request_id();
+        let resp = conn
+            .execute::<_, (u64, String)>("INSERT INTO sql_test (name) VALUES (?)", &("a",))
+            .await
+            .unwrap();
+        let info = resp.info.unwrap();
+        assert_eq!(info.row_count, 1);
+        assert_eq!(info.autoincrement_ids.len(), 1);
+        let id = info.autoincrement_ids[0];
 
-            let result = TarantoolResp {
-                header,
-                cursor_ref: CursorRef {
-                    buffer_key,
-                    position: resp_reader.position(),
-                },
-            };
+        let resp: SqlResponse<(i64, String)> = conn
+            .execute("SELECT id, name FROM sql_test WHERE id = ?", &(id,))
+            .await
+            .unwrap();
+        assert_eq!(resp.rows, vec![(id, "a".into())]);
+        assert_eq!(resp.metadata.len(), 2);
+        assert_eq!(resp.metadata[0].name, "ID");
+        assert_eq!(resp.metadata[1].field_type, "string");
+    }
 
-            // resp_buf must be dropped before it was sent to prevent mutual access by receiver
-            // (if receivers gets the key before it was dropped it receives null)
-            drop(resp_buf);
+    #[tokio::test]
+    async fn prepare_test() {
+        let conn = conn().await;
 
-            let req = self.pending_requests.take(request_id).unwrap();
-            req.tx.send(result).unwrap();
-        }
+        let stmt = conn.prepare("SELECT ? + ?").await.unwrap();
+        assert_eq!(stmt.bind_count(), 2);
 
-        Ok(())
+        let resp: SqlResponse<(i64,)> = stmt.execute(&(1, 2)).await.unwrap();
+        assert_eq!(resp.rows, vec![(3,)]);
+        let resp: SqlResponse<(i64,)> = stmt.execute(&(3, 4)).await.unwrap();
+        assert_eq!(resp.rows, vec![(7,)]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::Connection;
-    use std::sync::Arc;
-    use std::time::Duration;
-    use tokio::time::timeout;
+    #[tokio::test]
+    async fn transaction_test() {
+        let conn = conn().await;
+        let _: Option<(u32, String)> = conn.delete(512, 0, &(300,)).await.unwrap();
 
-    const TESTING_HOST: &str = "localhost:3301";
+        let txn = conn.begin(TxnIsolation::Default, None).await.unwrap();
+        let _: Option<(u32, String)> = txn.insert(512, &(300, "txn")).await.unwrap();
+        let visible: Vec<(u32, String)> = conn
+            .select(512, 0, &(300,), IteratorType::Eq, 1, 0)
+            .await
+            .unwrap();
+        assert!(visible.is_empty());
+        txn.rollback().await.unwrap();
 
-    async fn conn() -> Arc<Connection> {
-        Connection::connect(TESTING_HOST).await.unwrap()
+        let txn = conn
+            .begin(TxnIsolation::ReadCommitted, Some(Duration::from_secs(5)))
+            .await
+            .unwrap();
+        let _: Option<(u32, String)> = txn.insert(512, &(300, "txn")).await.unwrap();
+        txn.commit().await.unwrap();
+
+        let visible: Vec<(u32, String)> = conn
+            .select(512, 0, &(300,), IteratorType::Eq, 1, 0)
+            .await
+            .unwrap();
+        assert_eq!(visible, vec![(300, "txn".into())]);
+
+        let _: Option<(u32, String)> = conn.delete(512, 0, &(300,)).await.unwrap();
     }
 
     #[tokio::test]
-    async fn client_test() {
+    async fn negotiation_test() {
+        let conn = conn().await;
+        assert!(conn.server_version().major >= 2);
+
+        let features = conn.server_features();
+        assert!(features.protocol_version > 0);
+        assert!(features.supports(Feature::Streams));
+        assert!(features.supports(Feature::Watchers));
+    }
+
+    #[tokio::test]
+    async fn watch_test() {
+        use futures::StreamExt;
+
         let conn = conn().await;
         let t = Duration::from_secs(2);
 
-        timeout(t, conn.auth("guest", None)).await.unwrap().unwrap();
+        let _: () = conn.call("box.broadcast", &("test_key", 1)).await.unwrap();
 
-        let (result,): (usize,) = timeout(t, conn.call("sum", &(1, 2)))
+        let mut stream = conn.watch("test_key").await.unwrap();
+        let value = timeout(t, stream.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(1));
+
+        let mut second = conn.watch("test_key").await.unwrap();
+        let value = timeout(t, second.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(1));
+
+        let _: () = conn.call("box.broadcast", &("test_key", 2)).await.unwrap();
+        let value = timeout(t, stream.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(2));
+        let value = timeout(t, second.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(2));
+    }
+
+    #[tokio::test]
+    async fn rewatch_test() {
+        use futures::StreamExt;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let t = Duration::from_secs(5);
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_WATCH);
+            server_conn.send_event("key", 1.into()).await;
+
+            // acknowledgements of the event may come before or after the unwatch
+            loop {
+                let req = server_conn.next_request().await;
+                if req.request_type == consts::IPROTO_UNWATCH {
+                    break;
+                }
+                assert_eq!(req.request_type, consts::IPROTO_WATCH);
+            }
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_WATCH);
+            server_conn.send_event("key", 2.into()).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let mut stream = conn.watch("key").await.unwrap();
+        let value = timeout(t, stream.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(1));
+
+        // the key is watched again right after the last stream is dropped
+        drop(stream);
+        let mut stream = conn.watch("key").await.unwrap();
+        loop {
+            let value = timeout(t, stream.next()).await.unwrap().unwrap();
+            if value == rmpv::Value::from(2) {
+                break;
+            }
+        }
+        let _server_conn = timeout(t, server_task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn event_with_full_channel_test() {
+        use futures::StreamExt;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let t = Duration::from_secs(5);
+
+        // the server stops reading after the watches, so the acknowledgements can't be queued
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            for _ in 0..2 {
+                let req = server_conn.next_request().await;
+                assert_eq!(req.request_type, consts::IPROTO_WATCH);
+            }
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            server_conn.send_event("a", 1.into()).await;
+            server_conn.send_event("b", 2.into()).await;
+            server_conn
+        });
+
+        let conn = Connection::builder(server_addr.to_string())
+            .request_channel_size(1)
+            .connect()
             .await
-            .unwrap()
             .unwrap();
+        let mut a = conn.watch("a").await.unwrap();
+        let mut b = conn.watch("b").await.unwrap();
+
+        let payload = "x".repeat(1024 * 1024);
+        for _ in 0..8 {
+            let conn = conn.clone();
+            let payload = payload.clone();
+            tokio::spawn(async move {
+                let _: Result<rmpv::Value, Error> = conn.call("sink", &(payload,)).await;
+            });
+        }
+
+        // the second event is read only if the first one didn't block the reader
+        let value = timeout(t, a.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(1));
+        let value = timeout(t, b.next()).await.unwrap().unwrap();
+        assert_eq!(value, rmpv::Value::from(2));
+        let _server_conn = timeout(t, server_task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn call_streaming_drain_first_test() {
+        use futures::StreamExt;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let t = Duration::from_secs(5);
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_CALL);
+            for i in 1..=3 {
+                let body = rmpv::Value::Map(vec![(
+                    consts::IPROTO_DATA.into(),
+                    rmpv::Value::Array(vec![i.into()]),
+                )]);
+                server_conn
+                    .send(consts::IPROTO_CHUNK as u32, req.sync, body)
+                    .await;
+            }
+            let data = rmpv::Value::Array(vec![3.into()]);
+            server_conn.reply_data(req.sync, data).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let (pushes, result) = conn.call_streaming("push_numbers", &(3,));
+
+        // the stream sends the request and ends after the final response on its own
+        let pushes = timeout(t, pushes.collect::<Vec<_>>()).await.unwrap();
+        assert_eq!(
+            pushes,
+            vec![
+                rmpv::Value::from(1),
+                rmpv::Value::from(2),
+                rmpv::Value::from(3)
+            ]
+        );
+        let (result,): (u64,) = timeout(t, result).await.unwrap().unwrap();
+        assert_eq!(result, 3);
+        let _server_conn = timeout(t, server_task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn call_streaming_test() {
+        use futures::StreamExt;
+
+        let conn = conn().await;
+
+        let (pushes, result) = conn.call_streaming("push_numbers", &(3,));
+        let (pushes, result) = tokio::join!(pushes.collect::<Vec<_>>(), result);
+        let (result,): (u64,) = result.unwrap();
         assert_eq!(result, 3);
+        assert_eq!(
+            pushes,
+            vec![
+                rmpv::Value::from(1),
+                rmpv::Value::from(2),
+                rmpv::Value::from(3)
+            ]
+        );
+    }
 
-        let (result,): (usize,) = timeout(t, conn.call("sum", &(1, 2)))
+    #[tokio::test]
+    async fn eval_test() {
+        let conn = conn().await;
+
+        let (sum, product): (i64, i64) = conn
+            .eval("local a, b = ... return a + b, a * b", &(3, 4))
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(result, 3);
+        assert_eq!((sum, product), (7, 12));
+
+        let result: (String,) = conn.eval("return box.info.status", &[(); 0]).await.unwrap();
+        assert_eq!(result.0, "running");
+    }
+
+    #[tokio::test]
+    async fn ping_test() {
+        let conn = conn().await;
+        let rtt = conn.ping().await.unwrap();
+        assert!(rtt < Duration::from_secs(2));
     }
 
     #[tokio::test]
@@ -398,4 +2368,905 @@ mod tests {
         let conn = conn().await;
         conn.auth("kek", None).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn reconnect_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            ..ReconnectPolicy::default()
+        };
+        let t = Duration::from_secs(5);
+
+        let server_task = tokio::spawn(async move {
+            // the first connection is lost before the select is answered
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_SELECT);
+            drop(server_conn);
+
+            // the select is resent, the call is not
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_SELECT);
+            let space_id = req.body_value(consts::IPROTO_SPACE_ID);
+            assert_eq!(space_id.and_then(rmpv::Value::as_u64), Some(512));
+            let tuples = rmpv::Value::Array(vec![rmpv::Value::Array(vec![1.into()])]);
+            server_conn.reply_data(req.sync, tuples).await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_CALL);
+            drop(server_conn);
+
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            server_conn
+        });
+
+        let conn = Connection::builder(server_addr.to_string())
+            .reconnect(policy)
+            .connect()
+            .await
+            .unwrap();
+
+        let tuples: Vec<(u64,)> =
+            timeout(t, conn.select(512, 0, &[(); 0], IteratorType::All, 10, 0))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(tuples, vec![(1,)]);
+
+        let result: Result<rmpv::Value, Error> = timeout(t, conn.call("not_idempotent", &[(); 0]))
+            .await
+            .unwrap();
+        assert!(matches!(result, Err(Error::Disconnected)));
+
+        timeout(t, conn.ping()).await.unwrap().unwrap();
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn no_reconnect_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let t = Duration::from_secs(5);
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let result = timeout(t, conn.ping()).await.unwrap();
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn builder_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            let username = req.body_value(consts::IPROTO_USER_NAME);
+            assert_eq!(username.and_then(rmpv::Value::as_str), Some("user"));
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+
+            // the call is never answered
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_CALL);
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            server_conn
+        });
+
+        let builder = Connection::builder(server_addr.to_string())
+            .credentials("user", Some("secret"))
+            .connect_timeout(Duration::from_secs(1))
+            .request_timeout(Duration::from_millis(100))
+            .read_buffer_size(1024)
+            .write_buffer_size(1024)
+            .request_channel_size(16)
+            .batch_size(0)
+            .tcp_nodelay(true)
+            .tcp_keepalive(Duration::from_secs(60))
+            .task_name_prefix("test");
+        let debug = format!("{builder:?}");
+        assert!(debug.contains("password: Some(..)") && !debug.contains("secret"));
+
+        let conn = builder.connect().await.unwrap();
+
+        let result: Result<rmpv::Value, Error> = conn.call("hang", &[(); 0]).await;
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        conn.ping().await.unwrap();
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn zero_request_channel_size_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            server_conn
+        });
+
+        // clamped to a single request instead of panicking in tokio
+        let conn = Connection::builder(server_addr.to_string())
+            .request_channel_size(0)
+            .connect()
+            .await
+            .unwrap();
+        conn.ping().await.unwrap();
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_send_releases_buffer_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        // after the handshake the server stops reading, so the socket, then the channel fill up
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            let _ = stop_rx.await;
+            drop(server_conn);
+        });
+
+        let conn = Connection::builder(server_addr.to_string())
+            .request_channel_size(1)
+            .request_timeout(Duration::from_millis(200))
+            .connect()
+            .await
+            .unwrap();
+        conn.ping().await.unwrap();
+
+        let payload = "x".repeat(1024 * 1024);
+        let calls = (0..32).map(|_| {
+            let conn = conn.clone();
+            let payload = payload.clone();
+            async move {
+                let result: Result<rmpv::Value, Error> = conn.call("sink", &(payload,)).await;
+                assert!(matches!(result, Err(Error::Timeout)));
+            }
+        });
+        futures::future::join_all(calls).await;
+
+        // writes stuck in the writer and the channel are dropped with the connection
+        stop_tx.send(()).unwrap();
+        server_task.await.unwrap();
+        timeout(Duration::from_secs(5), conn.await_err())
+            .await
+            .unwrap();
+
+        let keys = conn.write_buffer_keys.lock().unwrap().clone();
+        assert!(keys.len() > 32);
+        let released = async {
+            while keys.iter().any(|&key| conn.buffer_pool.get(key).is_some()) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        timeout(Duration::from_secs(5), released).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn request_timeout_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+
+            // both calls are answered after the client gave up on them
+            let timed_out = server_conn.next_request().await;
+            let cancelled = server_conn.next_request().await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            for req in [timed_out, cancelled] {
+                let data = rmpv::Value::Array(vec!["late".into()]);
+                server_conn.reply_data(req.sync, data).await;
+            }
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_EVAL);
+            let data = rmpv::Value::Array(vec!["fresh".into()]);
+            server_conn.reply_data(req.sync, data).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+
+        let options = RequestOptions {
+            timeout: Some(Duration::from_millis(20)),
+            ..RequestOptions::default()
+        };
+        let result: Result<(String,), Error> = conn.call_with("slow", &[(); 0], &options).await;
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        let cancelled = timeout(
+            Duration::from_millis(20),
+            conn.call::<_, (String,)>("slow", &[(); 0]),
+        )
+        .await;
+        assert!(cancelled.is_err());
+
+        let (result,): (String,) = timeout(Duration::from_secs(5), conn.eval("", &[(); 0]))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, "fresh");
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    /// Connects to a server answering the ping with `reply(sync)` and closing the connection,
+    /// returns the ping result.
+    async fn ping_with_reply(
+        reply: impl FnOnce(u64) -> Vec<u8> + Send + 'static,
+    ) -> Result<(), Error> {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            server_conn.write_raw(&reply(req.sync)).await;
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let result = timeout(Duration::from_secs(5), conn.ping())
+            .await
+            .expect("request must not hang")
+            .map(|_| ());
+        server_task.await.unwrap();
+
+        if let Err(Error::ConnectionError(err)) = &result {
+            let join_err = err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<tokio::task::JoinError>());
+            assert!(
+                !join_err.is_some_and(|err| err.is_panic()),
+                "reader panicked"
+            );
+        }
+        result
+    }
+
+    #[tokio::test]
+    async fn garbage_frames_test() {
+        // broken frames poison the connection
+        let result = ping_with_reply(|_| vec![0xCD, 0, 1, 0xC0, 0xC0]).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+
+        let result = ping_with_reply(|_| testing::frame(&[0xC1, 0xC1])).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+
+        let result = ping_with_reply(|_| testing::frame(&[0x81, 0x01, 0x01])).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+
+        // an oversized length prefix is not allocated for
+        let result = ping_with_reply(|_| vec![0xCE, 0xFF, 0xFF, 0xFF, 0xFF]).await;
+        assert!(matches!(&result, Err(Error::ConnectionError(err))
+            if err.kind() == std::io::ErrorKind::InvalidData));
+
+        // a salt too short to scramble the password is rejected with the greeting
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let server_task = tokio::spawn(async move {
+            server
+                .accept_with_greeting(&testing::greeting(&[7; 8]))
+                .await
+        });
+        let result = Connection::builder(server_addr.to_string())
+            .credentials("user", Some("secret"))
+            .connect()
+            .await;
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(std::io::ErrorKind::InvalidData)
+        );
+        let _server_conn = server_task.await.unwrap();
+
+        // a broken body fails only the request it belongs to
+        let result = ping_with_reply(|sync| {
+            let mut payload = testing::header(0x8000 + 3, sync);
+            payload.extend_from_slice(&[0x81, 0x31, 0xC1]);
+            testing::frame(&payload)
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::InvalidDecoding {
+                request_type: consts::IPROTO_PING,
+                ..
+            })
+        ));
+
+        let result = ping_with_reply(|sync| testing::frame(&testing::header(0x1234, sync))).await;
+        assert!(matches!(
+            result,
+            Err(Error::InvalidResponse {
+                request_type: consts::IPROTO_PING,
+                code: 0x1234
+            })
+        ));
+
+        // unknown header keys are skipped
+        let result = ping_with_reply(|sync| {
+            let mut payload = vec![0x83, 0x00, 0x00, 0x01, 0xCF];
+            payload.extend_from_slice(&sync.to_be_bytes());
+            payload.extend_from_slice(&[0x7F, 0xA3, b'a', b'b', b'c', 0x80]);
+            testing::frame(&payload)
+        })
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn random_frames_test() {
+        // xorshift, deterministic to make failures reproducible
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..200 {
+            let kind = next() % 3;
+            let len = (next() % 64) as usize;
+            let garbage: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            let code = [0, 0x8000 + (next() % 300) as u32, next() as u32][(next() % 3) as usize];
+
+            let result = ping_with_reply(move |sync| match kind {
+                // garbage instead of a frame
+                0 => garbage,
+                // garbage frame
+                1 => testing::frame(&garbage),
+                // garbage body
+                _ => {
+                    let mut payload = testing::header(code, sync);
+                    payload.extend_from_slice(&garbage);
+                    testing::frame(&payload)
+                }
+            })
+            .await;
+            // any outcome is fine as long as nothing panics or hangs
+            let _ = result;
+        }
+    }
+
+    #[tokio::test]
+    async fn error_code_test() {
+        let result = ping_with_reply(|sync| {
+            let mut payload = testing::header(0x8000 + consts::ER_READONLY as u32, sync);
+            let message = "Can't modify data on a read-only instance";
+            let body = rmpv::Value::Map(vec![(consts::IPROTO_ERROR_24.into(), message.into())]);
+            rmpv::encode::write_value(&mut payload, &body).unwrap();
+            testing::frame(&payload)
+        })
+        .await;
+
+        let err = result.unwrap_err();
+        assert_eq!(err.tarantool_code(), Some(TarantoolErrorCode::Readonly));
+        assert!(err.tarantool_code().unwrap().is_readonly());
+        assert_eq!(
+            err.to_string(),
+            "ER_READONLY: Can't modify data on a read-only instance"
+        );
+    }
+
+    /// Answers the `_vspace` and `_vindex` selects with a single space.
+    async fn reply_schema(server_conn: &mut testing::MockConn, space_name: &str) {
+        use rmpv::Value;
+
+        let req = server_conn.next_request().await;
+        assert_eq!(req.request_type, consts::IPROTO_SELECT);
+        assert_eq!(
+            req.body_value(consts::IPROTO_SPACE_ID),
+            Some(&Value::from(consts::VSPACE_ID))
+        );
+        let format = Value::Array(vec![Value::Map(vec![
+            ("name".into(), "id".into()),
+            ("type".into(), "unsigned".into()),
+        ])]);
+        let space = Value::Array(vec![
+            512.into(),
+            1.into(),
+            space_name.into(),
+            "memtx".into(),
+            0.into(),
+            Value::Map(vec![]),
+            format,
+        ]);
+        server_conn
+            .reply_data(req.sync, Value::Array(vec![space]))
+            .await;
+
+        let req = server_conn.next_request().await;
+        assert_eq!(
+            req.body_value(consts::IPROTO_SPACE_ID),
+            Some(&Value::from(consts::VINDEX_ID))
+        );
+        let parts = Value::Array(vec![Value::Map(vec![
+            ("field".into(), 0.into()),
+            ("type".into(), "unsigned".into()),
+        ])]);
+        let index = Value::Array(vec![
+            512.into(),
+            0.into(),
+            "primary".into(),
+            "tree".into(),
+            Value::Map(vec![("unique".into(), true.into())]),
+            parts,
+        ]);
+        server_conn
+            .reply_data(req.sync, Value::Array(vec![index]))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn schema_reload_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            server_conn.schema_version = Some(1);
+            reply_schema(&mut server_conn, "test").await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.schema_version = Some(2);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            reply_schema(&mut server_conn, "renamed").await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_CALL);
+            let body = rmpv::Value::Map(vec![(
+                consts::IPROTO_ERROR_24.into(),
+                "Wrong schema version".into(),
+            )]);
+            let code = 0x8000 + consts::ER_WRONG_SCHEMA_VERSION as u32;
+            server_conn.send(code, req.sync, body).await;
+            reply_schema(&mut server_conn, "reloaded").await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+
+        let schema = conn.schema().await.unwrap();
+        assert_eq!(schema.version(), 1);
+        let space = schema.space("test").unwrap();
+        assert_eq!(space.id, 512);
+        assert_eq!(space.field_no("id"), Some(0));
+        assert!(space.index("primary").unwrap().unique);
+
+        // served from the cache while the version stays the same
+        assert!(Arc::ptr_eq(&schema, &conn.schema().await.unwrap()));
+
+        conn.ping().await.unwrap();
+        let schema = conn.schema().await.unwrap();
+        assert_eq!(schema.version(), 2);
+        assert!(schema.space("renamed").is_some());
+
+        let result: Result<rmpv::Value, Error> = conn.call("func", &[(); 0]).await;
+        assert_eq!(
+            result.unwrap_err().tarantool_code(),
+            Some(TarantoolErrorCode::WrongSchemaVersion)
+        );
+        let schema = conn.schema().await.unwrap();
+        assert!(schema.space("reloaded").is_some());
+
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn space_and_index_names_test() {
+        use rmpv::Value;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_SELECT);
+            assert_eq!(
+                req.body_value(consts::IPROTO_SPACE_NAME),
+                Some(&Value::from("test"))
+            );
+            assert_eq!(
+                req.body_value(consts::IPROTO_INDEX_NAME),
+                Some(&Value::from("primary"))
+            );
+            assert!(req.body_value(consts::IPROTO_SPACE_ID).is_none());
+            server_conn.reply_data(req.sync, Value::Array(vec![])).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let tuples: Vec<Value> = conn
+            .select("test", "primary", &[(); 0], IteratorType::All, 10, 0)
+            .await
+            .unwrap();
+        assert!(tuples.is_empty());
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn space_and_index_names_fallback_test() {
+        use rmpv::Value;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            server_conn
+                .features
+                .retain(|&feature| feature != consts::IPROTO_FEATURE_SPACE_AND_INDEX_NAMES);
+            server_conn.schema_version = Some(1);
+            reply_schema(&mut server_conn, "test").await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_DELETE);
+            assert_eq!(
+                req.body_value(consts::IPROTO_SPACE_ID),
+                Some(&Value::from(512))
+            );
+            assert_eq!(
+                req.body_value(consts::IPROTO_INDEX_ID),
+                Some(&Value::from(0))
+            );
+            server_conn.reply_data(req.sync, Value::Array(vec![])).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let deleted: Option<Value> = conn.delete("test", "primary", &(1,)).await.unwrap();
+        assert!(deleted.is_none());
+
+        let result: Result<Option<Value>, Error> = conn.insert("missing", &(1,)).await;
+        assert!(matches!(result, Err(Error::NoSuchSpace(space)) if space == "'missing'"));
+
+        let result: Result<Option<Value>, Error> = conn.delete(512, "missing", &(1,)).await;
+        assert!(matches!(result, Err(Error::NoSuchIndex { .. })));
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn scan_test() {
+        use rmpv::Value;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let pages = [(None, vec![1, 2], "p2"), (Some("p2"), vec![3, 4], "p4")];
+            for (after, ids, position) in pages.into_iter().chain([(Some("p4"), vec![5], "p5")]) {
+                let req = server_conn.next_request().await;
+                assert_eq!(req.request_type, consts::IPROTO_SELECT);
+                assert_eq!(
+                    req.body_value(consts::IPROTO_FETCH_POSITION),
+                    Some(&Value::from(true))
+                );
+                assert_eq!(req.body_value(consts::IPROTO_LIMIT), Some(&Value::from(2)));
+                assert_eq!(
+                    req.body_value(consts::IPROTO_AFTER_POSITION),
+                    after.map(Value::from).as_ref()
+                );
+
+                let tuples = ids
+                    .into_iter()
+                    .map(|id| Value::Array(vec![id.into()]))
+                    .collect();
+                let body = Value::Map(vec![
+                    (consts::IPROTO_DATA.into(), Value::Array(tuples)),
+                    (consts::IPROTO_POSITION.into(), position.into()),
+                ]);
+                server_conn.reply(req.sync, body).await;
+            }
+
+            let req = server_conn.next_request().await;
+            assert_eq!(
+                req.body_value(consts::IPROTO_AFTER_TUPLE),
+                Some(&Value::Array(vec![5.into()]))
+            );
+            server_conn.reply_data(req.sync, Value::Array(vec![])).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+        let tuples: Vec<(u32,)> = conn
+            .scan::<_, (u32,)>(512, 0, &[(); 0], 2)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(tuples, [(1,), (2,), (3,), (4,), (5,)]);
+
+        let last = Value::Array(vec![5.into()]);
+        let page: SelectResponse<(u32,)> = conn
+            .select_page(
+                512,
+                0,
+                &[(); 0],
+                IteratorType::Ge,
+                2,
+                Some(After::Tuple(&last)),
+            )
+            .await
+            .unwrap();
+        assert!(page.tuples.is_empty());
+        assert!(page.position.is_none());
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unix_socket_test() {
+        let path = std::env::temp_dir().join(format!("iproto-{}.sock", std::process::id()));
+        let server = MockServer::bind_unix(&path);
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            let username = req.body_value(consts::IPROTO_USER_NAME);
+            assert_eq!(username.and_then(rmpv::Value::as_str), Some("user"));
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            server_conn
+        });
+
+        let uri = format!("user:secret@unix/:{}", path.display());
+        let conn = Connection::connect(uri).await.unwrap();
+        conn.ping().await.unwrap();
+
+        let _server_conn = server_task.await.unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn tls_test() {
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        use super::TlsOptions;
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+
+        let issue = |name: &str| {
+            let key = KeyPair::generate().unwrap();
+            let params = CertificateParams::new(vec![name.to_owned()]).unwrap();
+            let cert = params.signed_by(&key, &ca_cert, &ca_key).unwrap();
+            (cert, key)
+        };
+        let (server_cert, server_key) = issue("localhost");
+        let (client_cert, client_key) = issue("client");
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(ca_cert.der().clone()).unwrap();
+        let client_verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+            roots.into(),
+            provider.clone(),
+        )
+        .build()
+        .unwrap();
+        let server_config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_client_cert_verifier(client_verifier)
+            .with_single_cert(
+                vec![CertificateDer::from(server_cert.der().to_vec())],
+                PrivateKeyDer::Pkcs8(server_key.serialize_der().into()),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+
+        let server = MockServer::bind_tls(acceptor).await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            server_conn.auth_type = Some("pap-sha256".to_owned());
+
+            // the advertised method is trusted over TLS
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            assert_eq!(
+                req.body_value(consts::IPROTO_TUPLE),
+                Some(&rmpv::Value::Array(vec![
+                    "pap-sha256".into(),
+                    "secret".into()
+                ]))
+            );
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            server_conn
+        });
+
+        let options = TlsOptions::new()
+            .ca_pem(ca_cert.pem())
+            .client_cert_pem(client_cert.pem(), client_key.serialize_pem())
+            .server_name("localhost")
+            .ciphers(&["TLS13_AES_256_GCM_SHA384"]);
+        let conn = Connection::builder(server_addr.to_string())
+            .credentials("user", Some("secret"))
+            .tls(options.clone())
+            .connect()
+            .await
+            .unwrap();
+        conn.ping().await.unwrap();
+        let _server_conn = server_task.await.unwrap();
+
+        let result = Connection::builder(server_addr.to_string())
+            .tls(options.ciphers(&["TLS_UNKNOWN"]))
+            .connect()
+            .await;
+        assert!(result.is_err());
+
+        let result = Connection::builder(server_addr.to_string())
+            .tls(TlsOptions::new())
+            .connect()
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn auth_method_test() {
+        use rmpv::Value;
+
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            server_conn.auth_type = Some("pap-sha256".to_owned());
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            assert_eq!(
+                req.body_value(consts::IPROTO_TUPLE),
+                Some(&Value::Array(vec!["pap-sha256".into(), "secret".into()]))
+            );
+            server_conn.reply(req.sync, Value::Map(vec![])).await;
+
+            let req = server_conn.next_request().await;
+            let tuple = req
+                .body_value(consts::IPROTO_TUPLE)
+                .unwrap()
+                .as_array()
+                .unwrap();
+            assert_eq!(tuple[0].as_str(), Some("chap-sha1"));
+            assert_eq!(tuple[1].as_slice().map(<[u8]>::len), Some(20));
+            server_conn.reply(req.sync, Value::Map(vec![])).await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_PING);
+            server_conn.reply(req.sync, Value::Map(vec![])).await;
+            server_conn
+        });
+
+        let conn = Connection::connect(server_addr.to_string()).await.unwrap();
+
+        // the plain password is not sent just because the server asks for it
+        let result = conn.auth("user", Some("secret")).await;
+        assert!(matches!(
+            result,
+            Err(Error::InsecureAuthMethod(AuthMethod::PapSha256))
+        ));
+
+        conn.auth_with("user", Some("secret"), AuthMethod::PapSha256)
+            .await
+            .unwrap();
+        conn.auth_with("user", Some("secret"), AuthMethod::ChapSha1)
+            .await
+            .unwrap();
+        conn.ping().await.unwrap();
+
+        // a method unknown to the client is rejected without a request
+        conn.session.write().unwrap().server_features.auth_type = Some("ldap".to_owned());
+        let result = conn.auth("user", Some("secret")).await;
+        assert!(matches!(result, Err(Error::UnsupportedAuthMethod(method)) if method == "ldap"));
+        let _server_conn = server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reauth_on_reconnect_test() {
+        let server = MockServer::bind().await;
+        let server_addr = server.addr();
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            ..ReconnectPolicy::default()
+        };
+        let t = Duration::from_secs(5);
+
+        let server_task = tokio::spawn(async move {
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_SELECT);
+            drop(server_conn);
+
+            // the resent select waits until the new connection is authenticated
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            let username = req.body_value(consts::IPROTO_USER_NAME);
+            assert_eq!(username.and_then(rmpv::Value::as_str), Some("user"));
+            let early = timeout(Duration::from_millis(50), server_conn.next_request()).await;
+            assert!(early.is_err());
+            server_conn.reply(req.sync, rmpv::Value::Map(vec![])).await;
+
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_SELECT);
+            let tuples = rmpv::Value::Array(vec![rmpv::Value::Array(vec![1.into()])]);
+            server_conn.reply_data(req.sync, tuples).await;
+            drop(server_conn);
+
+            // credentials rejected during the handshake fail the reconnect attempt
+            let mut server_conn = server.accept().await;
+            let req = server_conn.next_request().await;
+            assert_eq!(req.request_type, consts::IPROTO_AUTH);
+            let body = rmpv::Value::Map(vec![(
+                consts::IPROTO_ERROR_24.into(),
+                "Incorrect password supplied for user 'user'".into(),
+            )]);
+            let code = 0x8000 + consts::ER_PASSWORD_MISMATCH as u32;
+            server_conn.send(code, req.sync, body).await;
+        });
+
+        let conn = Connection::builder(server_addr.to_string())
+            .credentials("user", Some("secret"))
+            .reconnect(ReconnectPolicy {
+                max_attempts: Some(1),
+                ..policy
+            })
+            .connect()
+            .await
+            .unwrap();
+
+        let tuples: Vec<(u64,)> =
+            timeout(t, conn.select(512, 0, &[(); 0], IteratorType::All, 10, 0))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(tuples, vec![(1,)]);
+
+        server_task.await.unwrap();
+        // lets the supervisor give up after the rejected handshake
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let result = timeout(t, conn.ping()).await.unwrap();
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+    }
 }
@@ -1,3 +1,6 @@
 pub(crate) mod consts;
+pub(crate) mod error_code;
+pub(crate) mod greeting;
 pub(crate) mod request;
 pub(crate) mod response;
+pub(crate) mod update;
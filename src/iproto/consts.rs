@@ -15,8 +15,11 @@ pub const IPROTO_CALL: u8 = 0x0a;
 pub const IPROTO_EXECUTE: u8 = 0x0b;
 pub const IPROTO_NOP: u8 = 0x0c;
 pub const IPROTO_PREPARE: u8 = 0x0d;
-pub const IPROTO_CONFIRM: u8 = 0x28;
-pub const IPROTO_ROLLBACK: u8 = 0x29;
+pub const IPROTO_BEGIN: u8 = 0x0e;
+pub const IPROTO_COMMIT: u8 = 0x0f;
+pub const IPROTO_ROLLBACK: u8 = 0x10;
+pub const IPROTO_RAFT_CONFIRM: u8 = 0x28;
+pub const IPROTO_RAFT_ROLLBACK: u8 = 0x29;
 pub const IPROTO_PING: u8 = 0x40;
 pub const IPROTO_JOIN: u8 = 0x41;
 pub const IPROTO_SUBSCRIBE: u8 = 0x42;
@@ -24,8 +27,13 @@ pub const IPROTO_VOTE_DEPRECATED: u8 = 0x43;
 pub const IPROTO_VOTE: u8 = 0x44;
 pub const IPROTO_FETCH_SNAPSHOT: u8 = 0x45;
 pub const IPROTO_REGISTER: u8 = 0x46;
+pub const IPROTO_ID: u8 = 0x49;
+pub const IPROTO_WATCH: u8 = 0x4a;
+pub const IPROTO_UNWATCH: u8 = 0x4b;
+pub const IPROTO_EVENT: u8 = 0x4c;
 
 pub const IPROTO_OK: u8 = 0x00;
+pub const IPROTO_CHUNK: u8 = 0x80;
 pub const IPROTO_REQUEST_TYPE: u8 = 0x00;
 pub const IPROTO_SYNC: u8 = 0x01;
 pub const IPROTO_REPLICA_ID: u8 = 0x02;
@@ -33,12 +41,14 @@ pub const IPROTO_LSN: u8 = 0x03;
 pub const IPROTO_TIMESTAMP: u8 = 0x04;
 pub const IPROTO_SCHEMA_VERSION: u8 = 0x05;
 pub const IPROTO_FLAGS: u8 = 0x09;
+pub const IPROTO_STREAM_ID: u8 = 0x0a;
 pub const IPROTO_SPACE_ID: u8 = 0x10;
 pub const IPROTO_INDEX_ID: u8 = 0x11;
 pub const IPROTO_LIMIT: u8 = 0x12;
 pub const IPROTO_OFFSET: u8 = 0x13;
 pub const IPROTO_ITERATOR: u8 = 0x14;
 pub const IPROTO_INDEX_BASE: u8 = 0x15;
+pub const IPROTO_FETCH_POSITION: u8 = 0x1f;
 pub const IPROTO_KEY: u8 = 0x20;
 pub const IPROTO_TUPLE: u8 = 0x21;
 pub const IPROTO_FUNCTION_NAME: u8 = 0x22;
@@ -57,16 +67,28 @@ pub const IPROTO_BALLOT_IS_ANON: u8 = 0x05;
 pub const IPROTO_BALLOT_IS_BOOTED: u8 = 0x06;
 pub const IPROTO_TUPLE_META: u8 = 0x2a;
 pub const IPROTO_OPTIONS: u8 = 0x2b;
+pub const IPROTO_AFTER_POSITION: u8 = 0x2e;
+pub const IPROTO_AFTER_TUPLE: u8 = 0x2f;
 pub const IPROTO_DATA: u8 = 0x30;
 pub const IPROTO_ERROR_24: u8 = 0x31;
 pub const IPROTO_METADATA: u8 = 0x32;
 pub const IPROTO_BIND_METADATA: u8 = 0x33;
 pub const IPROTO_BIND_COUNT: u8 = 0x34;
+pub const IPROTO_POSITION: u8 = 0x35;
 pub const IPROTO_SQL_TEXT: u8 = 0x40;
 pub const IPROTO_SQL_BIND: u8 = 0x41;
 pub const IPROTO_SQL_INFO: u8 = 0x42;
 pub const IPROTO_STMT_ID: u8 = 0x43;
 pub const IPROTO_ERROR: u8 = 0x52;
+pub const IPROTO_VERSION: u8 = 0x54;
+pub const IPROTO_FEATURES: u8 = 0x55;
+pub const IPROTO_TIMEOUT: u8 = 0x56;
+pub const IPROTO_EVENT_KEY: u8 = 0x57;
+pub const IPROTO_EVENT_DATA: u8 = 0x58;
+pub const IPROTO_TXN_ISOLATION: u8 = 0x59;
+pub const IPROTO_AUTH_TYPE: u8 = 0x5b;
+pub const IPROTO_SPACE_NAME: u8 = 0x5e;
+pub const IPROTO_INDEX_NAME: u8 = 0x5f;
 pub const IPROTO_FIELD_NAME: u8 = 0x00;
 pub const IPROTO_FIELD_TYPE: u8 = 0x01;
 pub const IPROTO_FIELD_COLL: u8 = 0x02;
@@ -74,6 +96,31 @@ pub const IPROTO_FIELD_IS_NULLABLE: u8 = 0x03;
 pub const IPROTO_FIELD_IS_AUTOINCREMENT: u8 = 0x04;
 pub const IPROTO_FIELD_SPAN: u8 = 0x05;
 
+pub const IPROTO_FEATURE_STREAMS: u8 = 0;
+pub const IPROTO_FEATURE_TRANSACTIONS: u8 = 1;
+pub const IPROTO_FEATURE_ERROR_EXTENSION: u8 = 2;
+pub const IPROTO_FEATURE_WATCHERS: u8 = 3;
+pub const IPROTO_FEATURE_PAGINATION: u8 = 4;
+pub const IPROTO_FEATURE_SPACE_AND_INDEX_NAMES: u8 = 5;
+pub const IPROTO_FEATURE_WATCH_ONCE: u8 = 6;
+pub const IPROTO_FEATURE_DML_TUPLE_EXTENSION: u8 = 7;
+pub const IPROTO_FEATURE_CALL_RET_TUPLE_EXTENSION: u8 = 8;
+pub const IPROTO_FEATURE_CALL_ARG_TUPLE_EXTENSION: u8 = 9;
+
+pub const SQL_INFO_ROW_COUNT: u8 = 0x00;
+pub const SQL_INFO_AUTOINCREMENT_IDS: u8 = 0x01;
+
+// system views the schema is loaded from
+pub const VSPACE_ID: u32 = 281;
+pub const VINDEX_ID: u32 = 289;
+
+// MsgPack extension types
+pub const MP_DECIMAL: i8 = 1;
+pub const MP_UUID: i8 = 2;
+pub const MP_ERROR: i8 = 3;
+pub const MP_DATETIME: i8 = 4;
+pub const MP_INTERVAL: i8 = 6;
+
 pub const MP_ERROR_STACK: u8 = 0x00;
 pub const MP_ERROR_TYPE: u8 = 0x00;
 pub const MP_ERROR_FILE: u8 = 0x01;
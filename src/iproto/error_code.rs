@@ -0,0 +1,580 @@
+use crate::iproto::consts;
+use std::fmt;
+
+macro_rules! error_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:ident,)*) => {
+        /// Tarantool error codes, see `box/errcode.h`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum TarantoolErrorCode {
+            $($(#[$meta])* $name,)*
+            /// code unknown to this version of the crate
+            Other(u32),
+        }
+
+        impl TarantoolErrorCode {
+            pub fn from_code(code: u32) -> Self {
+                match code {
+                    $(code if code == consts::$code as u32 => Self::$name,)*
+                    code => Self::Other(code),
+                }
+            }
+
+            pub fn code(self) -> u32 {
+                match self {
+                    $(Self::$name => consts::$code as u32,)*
+                    Self::Other(code) => code,
+                }
+            }
+
+            /// `ER_*` name of the code, `None` for [`TarantoolErrorCode::Other`].
+            pub fn name(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$name => Some(stringify!($code)),)*
+                    Self::Other(_) => None,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// `Unknown error`
+    Unknown = ER_UNKNOWN,
+    /// `Illegal parameters, %s`
+    IllegalParams = ER_ILLEGAL_PARAMS,
+    /// `Failed to allocate %u bytes in %s for %s`
+    MemoryIssue = ER_MEMORY_ISSUE,
+    /// `Duplicate key exists in unique index "%s" in space "%s" with old tuple - %s and new tuple - %s`
+    TupleFound = ER_TUPLE_FOUND,
+    /// `Tuple doesn't exist in index '%s' in space '%s'`
+    TupleNotFound = ER_TUPLE_NOT_FOUND,
+    /// `%s does not support %s`
+    Unsupported = ER_UNSUPPORTED,
+    /// `Can't modify data on a replication slave. My master is: %s`
+    Nonmaster = ER_NONMASTER,
+    /// `Can't modify data on a read-only instance`
+    Readonly = ER_READONLY,
+    /// `Error injection '%s'`
+    Injection = ER_INJECTION,
+    /// `Failed to create space '%s': %s`
+    CreateSpace = ER_CREATE_SPACE,
+    /// `Space '%s' already exists`
+    SpaceExists = ER_SPACE_EXISTS,
+    /// `Can't drop space '%s': %s`
+    DropSpace = ER_DROP_SPACE,
+    /// `Can't modify space '%s': %s`
+    AlterSpace = ER_ALTER_SPACE,
+    /// `Unsupported index type supplied for index '%s' in space '%s'`
+    IndexType = ER_INDEX_TYPE,
+    /// `Can't create or modify index '%s' in space '%s': %s`
+    ModifyIndex = ER_MODIFY_INDEX,
+    /// `Can't drop the primary key in a system space, space '%s'`
+    LastDrop = ER_LAST_DROP,
+    /// `Tuple format limit reached: %u`
+    TupleFormatLimit = ER_TUPLE_FORMAT_LIMIT,
+    /// `Can't drop primary key in space '%s' while secondary keys exist`
+    DropPrimaryKey = ER_DROP_PRIMARY_KEY,
+    /// `Supplied key type of part %u does not match index part type: expected %s`
+    KeyPartType = ER_KEY_PART_TYPE,
+    /// `Invalid key part count in an exact match (expected %u, got %u)`
+    ExactMatch = ER_EXACT_MATCH,
+    /// `Invalid MsgPack - %s`
+    InvalidMsgpack = ER_INVALID_MSGPACK,
+    /// `msgpack.encode: can not encode Lua type '%s'`
+    ProcRet = ER_PROC_RET,
+    /// `Tuple/Key must be MsgPack array`
+    TupleNotArray = ER_TUPLE_NOT_ARRAY,
+    /// `Tuple field %s type does not match one required by operation: expected %s, got %s`
+    FieldType = ER_FIELD_TYPE,
+    /// `Field %s has type '%s' in one index, but type '%s' in another`
+    IndexPartTypeMismatch = ER_INDEX_PART_TYPE_MISMATCH,
+    /// `SPLICE error on field %s: %s`
+    UpdateSplice = ER_UPDATE_SPLICE,
+    /// `Argument type in operation '%c' on field %s does not match field type: expected %s`
+    UpdateArgType = ER_UPDATE_ARG_TYPE,
+    /// `Field %s has type '%s' in space format, but type '%s' in index definition`
+    FormatMismatchIndexPart = ER_FORMAT_MISMATCH_INDEX_PART,
+    /// `Unknown UPDATE operation #%d: %s`
+    UnknownUpdateOp = ER_UNKNOWN_UPDATE_OP,
+    /// `Field %s UPDATE error: %s`
+    UpdateField = ER_UPDATE_FIELD,
+    /// `Transaction is active at return from function`
+    FunctionTxActive = ER_FUNCTION_TX_ACTIVE,
+    /// `Invalid key part count (expected [0..%u], got %u)`
+    KeyPartCount = ER_KEY_PART_COUNT,
+    /// `%s`
+    ProcLua = ER_PROC_LUA,
+    /// `Procedure '%.*s' is not defined`
+    NoSuchProc = ER_NO_SUCH_PROC,
+    /// `Trigger '%s' doesn't exist`
+    NoSuchTrigger = ER_NO_SUCH_TRIGGER,
+    /// `No index #%u is defined in space '%s'`
+    NoSuchIndexId = ER_NO_SUCH_INDEX_ID,
+    /// `Space '%s' does not exist`
+    NoSuchSpace = ER_NO_SUCH_SPACE,
+    /// `Field %d was not found in the tuple`
+    NoSuchFieldNo = ER_NO_SUCH_FIELD_NO,
+    /// `Tuple field count %u does not match space field count %u`
+    ExactFieldCount = ER_EXACT_FIELD_COUNT,
+    /// `Tuple field %s required by space format is missing`
+    FieldMissing = ER_FIELD_MISSING,
+    /// `Failed to write to disk`
+    WalIo = ER_WAL_IO,
+    /// `Get() doesn't support partial keys and non-unique indexes`
+    MoreThanOneTuple = ER_MORE_THAN_ONE_TUPLE,
+    /// `s access to %s '%s' is denied for user '%s'`
+    AccessDenied = ER_ACCESS_DENIED,
+    /// `Failed to create user '%s': %s`
+    CreateUser = ER_CREATE_USER,
+    /// `Failed to drop user or role '%s': %s`
+    DropUser = ER_DROP_USER,
+    /// `User '%s' is not found`
+    NoSuchUser = ER_NO_SUCH_USER,
+    /// `User '%s' already exists`
+    UserExists = ER_USER_EXISTS,
+    /// `Incorrect password supplied for user '%s'`
+    PasswordMismatch = ER_PASSWORD_MISMATCH,
+    /// `Unknown request type %u`
+    UnknownRequestType = ER_UNKNOWN_REQUEST_TYPE,
+    /// `Unknown object type '%s'`
+    UnknownSchemaObject = ER_UNKNOWN_SCHEMA_OBJECT,
+    /// `Failed to create function '%s': %s`
+    CreateFunction = ER_CREATE_FUNCTION,
+    /// `Function '%s' does not exist`
+    NoSuchFunction = ER_NO_SUCH_FUNCTION,
+    /// `Function '%s' already exists`
+    FunctionExists = ER_FUNCTION_EXISTS,
+    /// `Invalid return value of space:before_replace trigger: expected tuple or nil, got %s`
+    BeforeReplaceRet = ER_BEFORE_REPLACE_RET,
+    /// ` not perform %s in a multi-statement transaction`
+    MultistatementTransaction = ER_MULTISTATEMENT_TRANSACTION,
+    /// `Trigger '%s' already exists`
+    TriggerExists = ER_TRIGGER_EXISTS,
+    /// `A limit on the total number of users has been reached: %u`
+    UserMax = ER_USER_MAX,
+    /// `Space engine '%s' does not exist`
+    NoSuchEngine = ER_NO_SUCH_ENGINE,
+    /// `Can't set option '%s' dynamically`
+    ReloadCfg = ER_RELOAD_CFG,
+    /// `Incorrect value for option '%s': %s`
+    Cfg = ER_CFG,
+    /// `Can not set a savepoint in an empty transaction`
+    SavepointEmptyTx = ER_SAVEPOINT_EMPTY_TX,
+    /// `Can not rollback to savepoint: the savepoint does not exist`
+    NoSuchSavepoint = ER_NO_SUCH_SAVEPOINT,
+    /// `Replica %s is not registered with replica set %s`
+    UnknownReplica = ER_UNKNOWN_REPLICA,
+    /// `Replica set UUID mismatch: expected %s, got %s`
+    ReplicasetUuidMismatch = ER_REPLICASET_UUID_MISMATCH,
+    /// `Invalid UUID: %s`
+    InvalidUuid = ER_INVALID_UUID,
+    /// `Can't reset replica set UUID: it is already assigned`
+    ReplicasetUuidIsRo = ER_REPLICASET_UUID_IS_RO,
+    /// `Instance UUID mismatch: expected %s, got %s`
+    InstanceUuidMismatch = ER_INSTANCE_UUID_MISMATCH,
+    /// `Can't initialize replica id with a reserved value %u`
+    ReplicaIdIsReserved = ER_REPLICA_ID_IS_RESERVED,
+    /// `Invalid LSN order for instance %u: previous LSN = %llu, new lsn = %llu`
+    InvalidOrder = ER_INVALID_ORDER,
+    /// `Missing mandatory field '%s' in request`
+    MissingRequestField = ER_MISSING_REQUEST_FIELD,
+    /// `Invalid identifier '%s' (expected printable symbols only or it is too long)`
+    Identifier = ER_IDENTIFIER,
+    /// `Can't drop function %u: %s`
+    DropFunction = ER_DROP_FUNCTION,
+    /// `Unknown iterator type '%s'`
+    IteratorType = ER_ITERATOR_TYPE,
+    /// `Replica count limit reached: %u`
+    ReplicaMax = ER_REPLICA_MAX,
+    /// `Failed to read xlog: %lld`
+    InvalidXlog = ER_INVALID_XLOG,
+    /// `Invalid xlog name: expected %lld got %lld`
+    InvalidXlogName = ER_INVALID_XLOG_NAME,
+    /// `Invalid xlog order: %lld and %lld`
+    InvalidXlogOrder = ER_INVALID_XLOG_ORDER,
+    /// `Connection is not established`
+    NoConnection = ER_NO_CONNECTION,
+    /// `Timeout exceeded`
+    Timeout = ER_TIMEOUT,
+    /// `Operation is not permitted when there is an active transaction`
+    ActiveTransaction = ER_ACTIVE_TRANSACTION,
+    /// `The transaction the cursor belongs to has ended`
+    CursorNoTransaction = ER_CURSOR_NO_TRANSACTION,
+    /// `A multi-statement transaction can not use multiple storage engines`
+    CrossEngineTransaction = ER_CROSS_ENGINE_TRANSACTION,
+    /// `Role '%s' is not found`
+    NoSuchRole = ER_NO_SUCH_ROLE,
+    /// `Role '%s' already exists`
+    RoleExists = ER_ROLE_EXISTS,
+    /// `Failed to create role '%s': %s`
+    CreateRole = ER_CREATE_ROLE,
+    /// `Index '%s' already exists`
+    IndexExists = ER_INDEX_EXISTS,
+    /// `Session is closed`
+    SessionClosed = ER_SESSION_CLOSED,
+    /// `Granting role '%s' to role '%s' would create a loop`
+    RoleLoop = ER_ROLE_LOOP,
+    /// `Incorrect grant arguments: %s`
+    Grant = ER_GRANT,
+    /// `User '%s' already has %s access on %s%s`
+    PrivGranted = ER_PRIV_GRANTED,
+    /// `User '%s' already has role '%s'`
+    RoleGranted = ER_ROLE_GRANTED,
+    /// `User '%s' does not have %s access on %s '%s'`
+    PrivNotGranted = ER_PRIV_NOT_GRANTED,
+    /// `User '%s' does not have role '%s'`
+    RoleNotGranted = ER_ROLE_NOT_GRANTED,
+    /// `Can't find snapshot`
+    MissingSnapshot = ER_MISSING_SNAPSHOT,
+    /// `Attempt to modify a tuple field which is part of index '%s' in space '%s'`
+    CantUpdatePrimaryKey = ER_CANT_UPDATE_PRIMARY_KEY,
+    /// `Integer overflow when performing '%c' operation on field %s`
+    UpdateIntegerOverflow = ER_UPDATE_INTEGER_OVERFLOW,
+    /// `Setting password for guest user has no effect`
+    GuestUserPassword = ER_GUEST_USER_PASSWORD,
+    /// `Transaction has been aborted by conflict`
+    TransactionConflict = ER_TRANSACTION_CONFLICT,
+    /// `Unsupported %s privilege '%s'`
+    UnsupportedPriv = ER_UNSUPPORTED_PRIV,
+    /// `Failed to dynamically load function '%s': %s`
+    LoadFunction = ER_LOAD_FUNCTION,
+    /// `Unsupported language '%s' specified for function '%s'`
+    FunctionLanguage = ER_FUNCTION_LANGUAGE,
+    /// `RTree: %s must be an array with %u (point) or %u (rectangle/box) numeric coordinates`
+    RtreeRect = ER_RTREE_RECT,
+    /// `%s`
+    ProcC = ER_PROC_C,
+    /// `Unknown RTREE index distance type %s`
+    UnknownRtreeIndexDistanceType = ER_UNKNOWN_RTREE_INDEX_DISTANCE_TYPE,
+    /// `%s`
+    Protocol = ER_PROTOCOL,
+    /// `Space %s has a unique secondary index and does not support UPSERT`
+    UpsertUniqueSecondaryKey = ER_UPSERT_UNIQUE_SECONDARY_KEY,
+    /// `Wrong record in _index space: got {%s}, expected {%s}`
+    WrongIndexRecord = ER_WRONG_INDEX_RECORD,
+    /// `Wrong index parts: %s; expected field1 id (number), field1 type (string), ...`
+    WrongIndexParts = ER_WRONG_INDEX_PARTS,
+    /// `Wrong index options (field %u): %s`
+    WrongIndexOptions = ER_WRONG_INDEX_OPTIONS,
+    /// `Wrong schema version, current: %d, in request: %u`
+    WrongSchemaVersion = ER_WRONG_SCHEMA_VERSION,
+    /// `Failed to allocate %u bytes for tuple: tuple is too large. Check 'memtx_max_tuple_size' configuration option.`
+    MemtxMaxTupleSize = ER_MEMTX_MAX_TUPLE_SIZE,
+    /// `Wrong space options (field %u): %s`
+    WrongSpaceOptions = ER_WRONG_SPACE_OPTIONS,
+    /// `Index '%s' (%s) of space '%s' (%s) does not support %s`
+    UnsupportedIndexFeature = ER_UNSUPPORTED_INDEX_FEATURE,
+    /// `View '%s' is read-only`
+    ViewIsRo = ER_VIEW_IS_RO,
+    /// `No active transaction`
+    NoTransaction = ER_NO_TRANSACTION,
+    /// `%s`
+    System = ER_SYSTEM,
+    /// `Instance bootstrap hasn't finished yet`
+    Loading = ER_LOADING,
+    /// `Connection to self`
+    ConnectionToSelf = ER_CONNECTION_TO_SELF,
+    /// `Key part is too long: %u of %u bytes`
+    KeyPartIsTooLong = ER_KEY_PART_IS_TOO_LONG,
+    /// `Compression error: %s`
+    Compression = ER_COMPRESSION,
+    /// `Snapshot is already in progress`
+    CheckpointInProgress = ER_CHECKPOINT_IN_PROGRESS,
+    /// `Can not execute a nested statement: nesting limit reached`
+    SubStmtMax = ER_SUB_STMT_MAX,
+    /// `Can not commit transaction in a nested statement`
+    CommitInSubStmt = ER_COMMIT_IN_SUB_STMT,
+    /// `Rollback called in a nested statement`
+    RollbackInSubStmt = ER_ROLLBACK_IN_SUB_STMT,
+    /// `Decompression error: %s`
+    Decompression = ER_DECOMPRESSION,
+    /// `Invalid xlog type: expected %s, got %s`
+    InvalidXlogType = ER_INVALID_XLOG_TYPE,
+    /// `Failed to lock WAL directory %s and hot_standby mode is off`
+    AlreadyRunning = ER_ALREADY_RUNNING,
+    /// `Indexed field count limit reached: %d indexed fields`
+    IndexFieldCountLimit = ER_INDEX_FIELD_COUNT_LIMIT,
+    /// `The local instance id %u is read-only`
+    LocalInstanceIdIsReadOnly = ER_LOCAL_INSTANCE_ID_IS_READ_ONLY,
+    /// `Backup is already in progress`
+    BackupInProgress = ER_BACKUP_IN_PROGRESS,
+    /// `The read view is aborted`
+    ReadViewAborted = ER_READ_VIEW_ABORTED,
+    /// `Invalid INDEX file %s: %s`
+    InvalidIndexFile = ER_INVALID_INDEX_FILE,
+    /// `Invalid RUN file: %s`
+    InvalidRunFile = ER_INVALID_RUN_FILE,
+    /// `Invalid VYLOG file: %s`
+    InvalidVylogFile = ER_INVALID_VYLOG_FILE,
+    /// `WAL has a rollback in progress`
+    CascadeRollback = ER_CASCADE_ROLLBACK,
+    /// `Timed out waiting for Vinyl memory quota`
+    VyQuotaTimeout = ER_VY_QUOTA_TIMEOUT,
+    /// `s index  does not support selects via a partial key (expected %u parts, got %u). Please Consider changing index type to TREE.`
+    PartialKey = ER_PARTIAL_KEY,
+    /// `Can't truncate a system space, space '%s'`
+    TruncateSystemSpace = ER_TRUNCATE_SYSTEM_SPACE,
+    /// `Failed to dynamically load module '%.*s': %s`
+    LoadModule = ER_LOAD_MODULE,
+    /// `Failed to allocate %u bytes for tuple: tuple is too large. Check 'vinyl_max_tuple_size' configuration option.`
+    VinylMaxTupleSize = ER_VINYL_MAX_TUPLE_SIZE,
+    /// `Wrong _schema version: expected 'major.minor[.patch]'`
+    WrongDdVersion = ER_WRONG_DD_VERSION,
+    /// `Wrong space format (field %u): %s`
+    WrongSpaceFormat = ER_WRONG_SPACE_FORMAT,
+    /// `Failed to create sequence '%s': %s`
+    CreateSequence = ER_CREATE_SEQUENCE,
+    /// `Can't modify sequence '%s': %s`
+    AlterSequence = ER_ALTER_SEQUENCE,
+    /// `Can't drop sequence '%s': %s`
+    DropSequence = ER_DROP_SEQUENCE,
+    /// `Sequence '%s' does not exist`
+    NoSuchSequence = ER_NO_SUCH_SEQUENCE,
+    /// `Sequence '%s' already exists`
+    SequenceExists = ER_SEQUENCE_EXISTS,
+    /// `Sequence '%s' has overflowed`
+    SequenceOverflow = ER_SEQUENCE_OVERFLOW,
+    /// `No index '%s' is defined in space '%s'`
+    NoSuchIndexName = ER_NO_SUCH_INDEX_NAME,
+    /// `Space field '%s' is duplicate`
+    SpaceFieldIsDuplicate = ER_SPACE_FIELD_IS_DUPLICATE,
+    /// `Failed to initialize collation: %s.`
+    CantCreateCollation = ER_CANT_CREATE_COLLATION,
+    /// `Wrong collation options (field %u): %s`
+    WrongCollationOptions = ER_WRONG_COLLATION_OPTIONS,
+    /// `Primary index of space '%s' can not contain nullable parts`
+    NullablePrimary = ER_NULLABLE_PRIMARY,
+    /// `Field '%s' was not found in space '%s' format`
+    NoSuchFieldNameInSpace = ER_NO_SUCH_FIELD_NAME_IN_SPACE,
+    /// `Transaction has been aborted by a fiber yield`
+    TransactionYield = ER_TRANSACTION_YIELD,
+    /// `Replication group '%s' does not exist`
+    NoSuchGroup = ER_NO_SUCH_GROUP,
+    /// `Bind value for parameter %s is out of range for type %s`
+    SqlBindValue = ER_SQL_BIND_VALUE,
+    /// `Bind value type %s for parameter %s is not supported`
+    SqlBindType = ER_SQL_BIND_TYPE,
+    /// `SQL bind parameter limit reached: %d`
+    SqlBindParameterMax = ER_SQL_BIND_PARAMETER_MAX,
+    /// `Failed to execute SQL statement: %s`
+    SqlExecute = ER_SQL_EXECUTE,
+    /// `Decimal overflow when performing operation '%c' on field %s`
+    UpdateDecimalOverflow = ER_UPDATE_DECIMAL_OVERFLOW,
+    /// `Parameter %s was not found in the statement`
+    SqlBindNotFound = ER_SQL_BIND_NOT_FOUND,
+    /// `Field %s contains %s on conflict action, but %s in index parts`
+    ActionMismatch = ER_ACTION_MISMATCH,
+    /// `Space declared as a view must have SQL statement`
+    ViewMissingSql = ER_VIEW_MISSING_SQL,
+    /// `Can not commit transaction: deferred foreign keys violations are not resolved`
+    ForeignKeyConstraint = ER_FOREIGN_KEY_CONSTRAINT,
+    /// `Module '%s' does not exist`
+    NoSuchModule = ER_NO_SUCH_MODULE,
+    /// `Collation '%s' does not exist`
+    NoSuchCollation = ER_NO_SUCH_COLLATION,
+    /// `Failed to create foreign key constraint '%s': %s`
+    CreateFkConstraint = ER_CREATE_FK_CONSTRAINT,
+    /// `Failed to drop foreign key constraint '%s': %s`
+    DropFkConstraint = ER_DROP_FK_CONSTRAINT,
+    /// `Constraint '%s' does not exist in space '%s'`
+    NoSuchConstraint = ER_NO_SUCH_CONSTRAINT,
+    /// `s constraint '%s' already exists in space '%s'`
+    ConstraintExists = ER_CONSTRAINT_EXISTS,
+    /// `Type mismatch: can not convert %s to %s`
+    SqlTypeMismatch = ER_SQL_TYPE_MISMATCH,
+    /// `Rowid is overflowed: too many entries in ephemeral space`
+    RowidOverflow = ER_ROWID_OVERFLOW,
+    /// `Can't drop collation %s : %s`
+    DropCollation = ER_DROP_COLLATION,
+    /// `Illegal mix of collations`
+    IllegalCollationMix = ER_ILLEGAL_COLLATION_MIX,
+    /// `Pragma '%s' does not exist`
+    SqlNoSuchPragma = ER_SQL_NO_SUCH_PRAGMA,
+    /// `Can’t resolve field '%s'`
+    SqlCantResolveField = ER_SQL_CANT_RESOLVE_FIELD,
+    /// `Index '%s' already exists in space '%s'`
+    IndexExistsInSpace = ER_INDEX_EXISTS_IN_SPACE,
+    /// `Inconsistent types: expected %s got %s`
+    InconsistentTypes = ER_INCONSISTENT_TYPES,
+    /// `Syntax error at line %d at or near position %d: %s`
+    SqlSyntaxWithPos = ER_SQL_SYNTAX_WITH_POS,
+    /// `Failed to parse SQL statement: parser stack limit reached`
+    SqlStackOverflow = ER_SQL_STACK_OVERFLOW,
+    /// `Failed to expand '*' in SELECT statement without FROM clause`
+    SqlSelectWildcard = ER_SQL_SELECT_WILDCARD,
+    /// `Failed to execute an empty SQL statement`
+    SqlStatementEmpty = ER_SQL_STATEMENT_EMPTY,
+    /// `At line %d at or near position %d: keyword '%.*s' is reserved. Please use double quotes if '%.*s' is an identifier.`
+    SqlKeywordIsReserved = ER_SQL_KEYWORD_IS_RESERVED,
+    /// `Syntax error at line %d near '%.*s'`
+    SqlSyntaxNearToken = ER_SQL_SYNTAX_NEAR_TOKEN,
+    /// `At line %d at or near position %d: unrecognized token '%.*s'`
+    SqlUnknownToken = ER_SQL_UNKNOWN_TOKEN,
+    /// `%s`
+    SqlParserGeneric = ER_SQL_PARSER_GENERIC,
+    /// `ANALYZE statement argument %s is not a base table`
+    SqlAnalyzeArgument = ER_SQL_ANALYZE_ARGUMENT,
+    /// `Failed to create space '%s': space column count %d exceeds the limit (%d)`
+    SqlColumnCountMax = ER_SQL_COLUMN_COUNT_MAX,
+    /// `Hex literal %s%s length %d exceeds the supported limit (%d)`
+    HexLiteralMax = ER_HEX_LITERAL_MAX,
+    /// `Integer literal %s%s exceeds the supported range [-9223372036854775808, 18446744073709551615]`
+    IntLiteralMax = ER_INT_LITERAL_MAX,
+    /// `s %d exceeds the limit (%d)`
+    SqlParserLimit = ER_SQL_PARSER_LIMIT,
+    /// `s are prohibited in an index definition`
+    IndexDefUnsupported = ER_INDEX_DEF_UNSUPPORTED,
+    /// `s are prohibited in a ck constraint definition`
+    CkDefUnsupported = ER_CK_DEF_UNSUPPORTED,
+    /// `Field %s is used as multikey in one index and as single key in another`
+    MultikeyIndexMismatch = ER_MULTIKEY_INDEX_MISMATCH,
+    /// `Failed to create check constraint '%s': %s`
+    CreateCkConstraint = ER_CREATE_CK_CONSTRAINT,
+    /// `Check constraint failed '%s': %s`
+    CkConstraintFailed = ER_CK_CONSTRAINT_FAILED,
+    /// `Unequal number of entries in row expression: left side has %u, but right side - %u`
+    SqlColumnCount = ER_SQL_COLUMN_COUNT,
+    /// `Failed to build a key for functional index '%s' of space '%s': %s`
+    FuncIndexFunc = ER_FUNC_INDEX_FUNC,
+    /// `Key format doesn't match one defined in functional index '%s' of space '%s': %s`
+    FuncIndexFormat = ER_FUNC_INDEX_FORMAT,
+    /// `Wrong functional index definition: %s`
+    FuncIndexParts = ER_FUNC_INDEX_PARTS,
+    /// `Field '%s' was not found in the tuple`
+    NoSuchFieldName = ER_NO_SUCH_FIELD_NAME,
+    /// `Wrong number of arguments is passed to %s(): expected %s, got %d`
+    FuncWrongArgCount = ER_FUNC_WRONG_ARG_COUNT,
+    /// `Trying to bootstrap a local read-only instance as master`
+    BootstrapReadonly = ER_BOOTSTRAP_READONLY,
+    /// `SQL expects exactly one argument returned from %s, got %d`
+    SqlFuncWrongRetCount = ER_SQL_FUNC_WRONG_RET_COUNT,
+    /// `Function '%s' returned value of invalid type: expected %s got %s`
+    FuncInvalidReturnType = ER_FUNC_INVALID_RETURN_TYPE,
+    /// ` line %d at or near position %d: %s`
+    SqlParserGenericWithPos = ER_SQL_PARSER_GENERIC_WITH_POS,
+    /// `Replica '%s' is not anonymous and cannot register.`
+    ReplicaNotAnon = ER_REPLICA_NOT_ANON,
+    /// `Couldn't find an instance to register this replica on.`
+    CannotRegister = ER_CANNOT_REGISTER,
+    /// `Session setting %s expected a value of type %s`
+    SessionSettingInvalidValue = ER_SESSION_SETTING_INVALID_VALUE,
+    /// `Failed to prepare SQL statement: %s`
+    SqlPrepare = ER_SQL_PREPARE,
+    /// `Prepared statement with id %u does not exist`
+    WrongQueryId = ER_WRONG_QUERY_ID,
+    /// `Sequence '%s' is not started`
+    SequenceNotStarted = ER_SEQUENCE_NOT_STARTED,
+    /// `Session setting %s doesn't exist`
+    NoSuchSessionSetting = ER_NO_SUCH_SESSION_SETTING,
+    /// `Found uncommitted sync transactions from other instance with id %u`
+    UncommittedForeignSyncTxns = ER_UNCOMMITTED_FOREIGN_SYNC_TXNS,
+    /// `CONFIRM message arrived for an unknown master id %d, expected %d`
+    SyncMasterMismatch = ER_SYNC_MASTER_MISMATCH,
+    /// `Quorum collection for a synchronous transaction is timed out`
+    SyncQuorumTimeout = ER_SYNC_QUORUM_TIMEOUT,
+    /// `A rollback for a synchronous transaction is received`
+    SyncRollback = ER_SYNC_ROLLBACK,
+    /// `Can't create tuple: metadata size %u is too big`
+    TupleMetadataIsTooBig = ER_TUPLE_METADATA_IS_TOO_BIG,
+    /// `%s`
+    XlogGap = ER_XLOG_GAP,
+    /// `Can't subscribe non-anonymous replica %s until join is done`
+    TooEarlySubscribe = ER_TOO_EARLY_SUBSCRIBE,
+    /// `Can't add AUTOINCREMENT: space %s can't feature more than one AUTOINCREMENT field`
+    SqlCantAddAutoinc = ER_SQL_CANT_ADD_AUTOINC,
+    /// `Couldn't wait for quorum %d: %s`
+    QuorumWait = ER_QUORUM_WAIT,
+    /// `Instance with replica id %u was promoted first`
+    InterferingPromote = ER_INTERFERING_PROMOTE,
+    /// `Elections were turned off`
+    ElectionDisabled = ER_ELECTION_DISABLED,
+    /// `Transaction was rolled back`
+    TxnRollback = ER_TXN_ROLLBACK,
+    /// `The instance is not a leader. New leader is %u`
+    NotLeader = ER_NOT_LEADER,
+    /// `The synchronous transaction queue doesn't belong to any instance`
+    SyncQueueUnclaimed = ER_SYNC_QUEUE_UNCLAIMED,
+    /// `The synchronous transaction queue belongs to other instance with id %u`
+    SyncQueueForeign = ER_SYNC_QUEUE_FOREIGN,
+    /// `Unable to process %s request in stream`
+    UnableToProcessInStream = ER_UNABLE_TO_PROCESS_IN_STREAM,
+    /// `Unable to process %s request out of stream`
+    UnableToProcessOutOfStream = ER_UNABLE_TO_PROCESS_OUT_OF_STREAM,
+    /// `Transaction has been aborted by timeout`
+    TransactionTimeout = ER_TRANSACTION_TIMEOUT,
+    /// `Operation is not permitted if timer is already running`
+    ActiveTimer = ER_ACTIVE_TIMER,
+}
+
+impl TarantoolErrorCode {
+    /// The request may succeed if repeated later on the same instance.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::Loading
+                | Self::Timeout
+                | Self::NoConnection
+                | Self::TransactionConflict
+                | Self::WrongSchemaVersion
+                | Self::VyQuotaTimeout
+                | Self::SyncQuorumTimeout
+                | Self::SyncRollback
+                | Self::QuorumWait
+                | Self::SyncQueueUnclaimed
+        )
+    }
+
+    /// The instance does not accept writes, the request should go to another one.
+    pub fn is_readonly(self) -> bool {
+        matches!(self, Self::Readonly | Self::Nonmaster)
+    }
+
+    /// The instance is not the replicaset leader, the request should go to the leader.
+    pub fn is_not_leader(self) -> bool {
+        matches!(self, Self::NotLeader)
+    }
+}
+
+impl From<u32> for TarantoolErrorCode {
+    fn from(code: u32) -> Self {
+        Self::from_code(code)
+    }
+}
+
+impl fmt::Display for TarantoolErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "error code {}", self.code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TarantoolErrorCode;
+    use crate::iproto::consts;
+
+    #[test]
+    fn from_code_test() {
+        let code = TarantoolErrorCode::from_code(consts::ER_TUPLE_FOUND as u32);
+        assert_eq!(code, TarantoolErrorCode::TupleFound);
+        assert_eq!(code.code(), 3);
+        assert_eq!(code.name(), Some("ER_TUPLE_FOUND"));
+        assert_eq!(code.to_string(), "ER_TUPLE_FOUND");
+
+        assert!(TarantoolErrorCode::from_code(consts::ER_READONLY as u32).is_readonly());
+        assert!(TarantoolErrorCode::from_code(consts::ER_NOT_LEADER as u32).is_not_leader());
+        assert!(TarantoolErrorCode::from_code(consts::ER_LOADING as u32).is_retryable());
+        assert!(!code.is_retryable());
+
+        let code = TarantoolErrorCode::from_code(4000);
+        assert_eq!(code, TarantoolErrorCode::Other(4000));
+        assert_eq!(code.code(), 4000);
+        assert_eq!(code.name(), None);
+        assert_eq!(code.to_string(), "error code 4000");
+
+        let code = TarantoolErrorCode::from_code(consts::ER_UNKNOWN as u32);
+        assert_eq!(code.name(), Some("ER_UNKNOWN"));
+    }
+}
@@ -0,0 +1,133 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64Engine;
+use std::fmt;
+use std::io;
+
+pub const GREETING_SIZE: usize = 128;
+
+/// chap-sha1 scrambles the password with the first 20 bytes of the salt.
+const MIN_SALT_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServerVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ServerVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses versions like `2.10.0` or `3.1.0-entrypoint-0-g1a2b3c`.
+    fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.split('.').map(|part| {
+            let digits_end = part
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(part.len());
+            part[..digits_end].parse::<u32>().ok()
+        });
+
+        let major = parts.next()??;
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Greeting sent by the server right after the connection is accepted:
+/// `Tarantool <version> (Binary) <instance uuid>` line followed by the salt line.
+#[derive(Debug, Clone)]
+pub struct Greeting {
+    pub version: ServerVersion,
+    pub salt: Vec<u8>,
+}
+
+impl Greeting {
+    pub fn decode(raw: &[u8; GREETING_SIZE]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_owned());
+
+        let (version_line, salt_line) = raw.split_at(GREETING_SIZE / 2);
+        let version_line =
+            std::str::from_utf8(version_line).map_err(|_| invalid("invalid greeting"))?;
+        let mut words = version_line.split_whitespace();
+        if words.next() != Some("Tarantool") {
+            return Err(invalid("invalid greeting"));
+        }
+
+        let version = words
+            .next()
+            .and_then(ServerVersion::parse)
+            .ok_or_else(|| invalid("invalid server version in greeting"))?;
+        // console ports greet with `(Lua console)`
+        if words.next() != Some("(Binary)") {
+            return Err(invalid("server does not speak binary protocol"));
+        }
+
+        let salt_b64 = std::str::from_utf8(&salt_line[..44])
+            .map_err(|_| invalid("invalid greeting salt"))?
+            .trim();
+        let salt = Base64Engine
+            .decode(salt_b64)
+            .map_err(|_| invalid("invalid greeting salt"))?;
+        if salt.len() < MIN_SALT_SIZE {
+            return Err(invalid("greeting salt is too short"));
+        }
+
+        Ok(Self { version, salt })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GREETING_SIZE, Greeting, ServerVersion};
+
+    fn raw_greeting(version_line: &str, salt: &str) -> [u8; GREETING_SIZE] {
+        let mut raw = [b' '; GREETING_SIZE];
+        raw[..version_line.len()].copy_from_slice(version_line.as_bytes());
+        raw[63] = b'\n';
+        raw[64..64 + salt.len()].copy_from_slice(salt.as_bytes());
+        raw[127] = b'\n';
+        raw
+    }
+
+    #[test]
+    fn decode_test() {
+        let raw = raw_greeting(
+            "Tarantool 2.10.4 (Binary) 7170b4af-c72f-4f07-8729-08fc678543a5",
+            "dGVzdHRlc3R0ZXN0dGVzdHRlc3R0ZXN0dGVzdHRlc3Q=",
+        );
+        let greeting = Greeting::decode(&raw).unwrap();
+        assert_eq!(greeting.version, ServerVersion::new(2, 10, 4));
+        assert_eq!(greeting.salt, b"testtesttesttesttesttesttesttest");
+
+        let raw = raw_greeting(
+            "Tarantool 3.1.0-entrypoint-12-gabc (Binary)",
+            "dGVzdHRlc3R0ZXN0dGVzdHRlc3R0ZXN0dGVzdHRlc3Q=",
+        );
+        let greeting = Greeting::decode(&raw).unwrap();
+        assert_eq!(greeting.version, ServerVersion::new(3, 1, 0));
+
+        // chap-sha1 needs at least 20 bytes of salt
+        let version_line = "Tarantool 2.10.4 (Binary) 7170b4af-c72f-4f07-8729-08fc678543a5";
+        assert!(Greeting::decode(&raw_greeting(version_line, "")).is_err());
+        assert!(Greeting::decode(&raw_greeting(version_line, "dGVzdHRlc3R0ZXN0dGVzdA==")).is_err());
+
+        let raw = raw_greeting("Redis 7.0.0", "");
+        assert!(Greeting::decode(&raw).is_err());
+
+        let raw = raw_greeting("Tarantool 2.10.4 (Lua console)", "");
+        assert!(Greeting::decode(&raw).is_err());
+    }
+}
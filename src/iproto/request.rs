@@ -1,16 +1,27 @@
 use crate::iproto::consts;
+use crate::iproto::response::Position;
+use crate::iproto::update::UpdateOps;
 use rmp::encode;
 use rmp::encode::ValueWriteError;
 use rmp_serde::encode::Error;
 use serde::Serialize;
 use sha1::{Digest, Sha1};
+use std::fmt;
 use std::io::Write;
+use std::time::Duration;
 
 pub trait Request<W: Write> {
     const REQUEST_TYPE: u8;
 
+    /// whether resending the request after a lost connection is safe by default
+    const IDEMPOTENT: bool = false;
+
     fn request_id(&self) -> usize;
 
+    fn stream_id(&self) -> Option<u64> {
+        None
+    }
+
     fn encode(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
         self.encode_header(wr)?;
         self.encode_body(wr)?;
@@ -18,7 +29,8 @@ pub trait Request<W: Write> {
     }
 
     fn encode_header(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
-        encode::write_map_len(wr, 2)?;
+        let stream_id = self.stream_id();
+        encode::write_map_len(wr, if stream_id.is_some() { 3 } else { 2 })?;
 
         encode::write_pfix(wr, consts::IPROTO_REQUEST_TYPE)
             .map_err(ValueWriteError::InvalidMarkerWrite)?;
@@ -27,18 +39,197 @@ pub trait Request<W: Write> {
         encode::write_pfix(wr, consts::IPROTO_SYNC).map_err(ValueWriteError::InvalidMarkerWrite)?;
         encode::write_u64(wr, self.request_id() as u64)?;
 
+        if let Some(stream_id) = stream_id {
+            encode::write_pfix(wr, consts::IPROTO_STREAM_ID)
+                .map_err(ValueWriteError::InvalidMarkerWrite)?;
+            encode::write_uint(wr, stream_id)?;
+        }
+
         Ok(())
     }
 
     fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error>;
 }
 
+/// Sends the wrapped request within a stream.
+pub struct Streamed<R> {
+    stream_id: u64,
+    request: R,
+}
+
+impl<R> Streamed<R> {
+    pub fn new(stream_id: u64, request: R) -> Self {
+        Streamed { stream_id, request }
+    }
+}
+
+impl<R: Request<W>, W: Write> Request<W> for Streamed<R> {
+    const REQUEST_TYPE: u8 = R::REQUEST_TYPE;
+
+    fn request_id(&self) -> usize {
+        self.request.request_id()
+    }
+
+    fn stream_id(&self) -> Option<u64> {
+        Some(self.stream_id)
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        self.request.encode_body(wr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Feature {
+    Streams = consts::IPROTO_FEATURE_STREAMS,
+    Transactions = consts::IPROTO_FEATURE_TRANSACTIONS,
+    ErrorExtension = consts::IPROTO_FEATURE_ERROR_EXTENSION,
+    Watchers = consts::IPROTO_FEATURE_WATCHERS,
+    Pagination = consts::IPROTO_FEATURE_PAGINATION,
+    SpaceAndIndexNames = consts::IPROTO_FEATURE_SPACE_AND_INDEX_NAMES,
+    WatchOnce = consts::IPROTO_FEATURE_WATCH_ONCE,
+    DmlTupleExtension = consts::IPROTO_FEATURE_DML_TUPLE_EXTENSION,
+    CallRetTupleExtension = consts::IPROTO_FEATURE_CALL_RET_TUPLE_EXTENSION,
+    CallArgTupleExtension = consts::IPROTO_FEATURE_CALL_ARG_TUPLE_EXTENSION,
+}
+
+impl Feature {
+    pub fn from_id(id: u64) -> Option<Self> {
+        const FEATURES: [Feature; 10] = [
+            Feature::Streams,
+            Feature::Transactions,
+            Feature::ErrorExtension,
+            Feature::Watchers,
+            Feature::Pagination,
+            Feature::SpaceAndIndexNames,
+            Feature::WatchOnce,
+            Feature::DmlTupleExtension,
+            Feature::CallRetTupleExtension,
+            Feature::CallArgTupleExtension,
+        ];
+        FEATURES.into_iter().find(|feature| *feature as u64 == id)
+    }
+}
+
+pub const PROTOCOL_VERSION: u64 = 6;
+
+pub const CLIENT_FEATURES: &[Feature] = &[
+    Feature::Streams,
+    Feature::Transactions,
+    Feature::ErrorExtension,
+    Feature::Watchers,
+    Feature::Pagination,
+    Feature::SpaceAndIndexNames,
+    Feature::WatchOnce,
+];
+
+pub struct Id {
+    request_id: usize,
+}
+
+impl Id {
+    pub fn new(request_id: usize) -> Self {
+        Id { request_id }
+    }
+}
+
+impl<W: Write> Request<W> for Id {
+    const REQUEST_TYPE: u8 = consts::IPROTO_ID;
+    const IDEMPOTENT: bool = true;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 2)?;
+
+        encode::write_pfix(wr, consts::IPROTO_VERSION)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_uint(wr, PROTOCOL_VERSION)?;
+
+        encode::write_pfix(wr, consts::IPROTO_FEATURES)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_array_len(wr, CLIENT_FEATURES.len() as u32)?;
+        for feature in CLIENT_FEATURES {
+            encode::write_pfix(wr, *feature as u8).map_err(ValueWriteError::InvalidMarkerWrite)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Subscribes to a key or acknowledges the last received event.
+/// The server does not reply to this request.
+pub struct Watch<'a> {
+    key: &'a str,
+}
+
+impl<'a> Watch<'a> {
+    pub fn new(key: &'a str) -> Self {
+        Watch { key }
+    }
+}
+
+impl<W: Write> Request<W> for Watch<'_> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_WATCH;
+
+    fn request_id(&self) -> usize {
+        0
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode_event_key(wr, self.key)
+    }
+}
+
+/// Unsubscribes from a key. The server does not reply to this request.
+pub struct Unwatch<'a> {
+    key: &'a str,
+}
+
+impl<'a> Unwatch<'a> {
+    pub fn new(key: &'a str) -> Self {
+        Unwatch { key }
+    }
+}
+
+impl<W: Write> Request<W> for Unwatch<'_> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_UNWATCH;
+
+    fn request_id(&self) -> usize {
+        0
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode_event_key(wr, self.key)
+    }
+}
+
+fn encode_event_key<W: Write>(wr: &mut W, key: &str) -> Result<(), rmp_serde::encode::Error> {
+    encode::write_map_len(wr, 1)?;
+
+    encode::write_pfix(wr, consts::IPROTO_EVENT_KEY)
+        .map_err(ValueWriteError::InvalidMarkerWrite)?;
+    encode::write_str(wr, key)?;
+
+    Ok(())
+}
+
 pub struct Ping {
     request_id: usize,
 }
 
+impl Ping {
+    pub fn new(request_id: usize) -> Self {
+        Ping { request_id }
+    }
+}
+
 impl<W: Write> Request<W> for Ping {
     const REQUEST_TYPE: u8 = consts::IPROTO_PING;
+    const IDEMPOTENT: bool = true;
 
     fn request_id(&self) -> usize {
         self.request_id
@@ -88,14 +279,648 @@ impl<T: Serialize, W: Write> Request<W> for Call<'_, T> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum IteratorType {
+    #[default]
+    Eq = 0,
+    Req = 1,
+    All = 2,
+    Lt = 3,
+    Le = 4,
+    Ge = 5,
+    Gt = 6,
+    BitsAllSet = 7,
+    BitsAnySet = 8,
+    BitsAllNotSet = 9,
+    Overlaps = 10,
+    Neighbor = 11,
+}
+
+/// Space addressed either by id or by name.
+/// Names are sent as is to servers supporting [`Feature::SpaceAndIndexNames`]
+/// and resolved through the schema otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceRef<'a> {
+    Id(u32),
+    Name(&'a str),
+}
+
+impl SpaceRef<'_> {
+    fn encode<W: Write>(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        match self {
+            SpaceRef::Id(id) => {
+                encode::write_pfix(wr, consts::IPROTO_SPACE_ID)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                encode::write_uint(wr, *id as u64)?;
+            }
+            SpaceRef::Name(name) => {
+                encode::write_pfix(wr, consts::IPROTO_SPACE_NAME)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                encode::write_str(wr, name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<u32> for SpaceRef<'_> {
+    fn from(id: u32) -> Self {
+        SpaceRef::Id(id)
+    }
+}
+
+impl<'a> From<&'a str> for SpaceRef<'a> {
+    fn from(name: &'a str) -> Self {
+        SpaceRef::Name(name)
+    }
+}
+
+impl fmt::Display for SpaceRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpaceRef::Id(id) => write!(f, "{id}"),
+            SpaceRef::Name(name) => write!(f, "'{name}'"),
+        }
+    }
+}
+
+/// Index addressed either by id or by name, see [`SpaceRef`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexRef<'a> {
+    Id(u32),
+    Name(&'a str),
+}
+
+impl IndexRef<'_> {
+    fn encode<W: Write>(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        match self {
+            IndexRef::Id(id) => {
+                encode::write_pfix(wr, consts::IPROTO_INDEX_ID)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                encode::write_uint(wr, *id as u64)?;
+            }
+            IndexRef::Name(name) => {
+                encode::write_pfix(wr, consts::IPROTO_INDEX_NAME)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                encode::write_str(wr, name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<u32> for IndexRef<'_> {
+    fn from(id: u32) -> Self {
+        IndexRef::Id(id)
+    }
+}
+
+impl<'a> From<&'a str> for IndexRef<'a> {
+    fn from(name: &'a str) -> Self {
+        IndexRef::Name(name)
+    }
+}
+
+impl fmt::Display for IndexRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexRef::Id(id) => write!(f, "{id}"),
+            IndexRef::Name(name) => write!(f, "'{name}'"),
+        }
+    }
+}
+
+/// Where a page of a select starts, exclusively.
+#[derive(Debug, Clone, Copy)]
+pub enum After<'a> {
+    /// position returned with the previous page
+    Position(&'a Position),
+    /// tuple or its key in the selected index
+    Tuple(&'a rmpv::Value),
+}
+
+pub struct Select<'a, K: Serialize> {
+    request_id: usize,
+    space: SpaceRef<'a>,
+    index: IndexRef<'a>,
+    key: &'a K,
+    iterator: IteratorType,
+    limit: u32,
+    offset: u32,
+    after: Option<After<'a>>,
+    fetch_position: bool,
+}
+
+impl<'a, K: Serialize> Select<'a, K> {
+    pub fn new(
+        request_id: usize,
+        space: SpaceRef<'a>,
+        index: IndexRef<'a>,
+        key: &'a K,
+        iterator: IteratorType,
+        limit: u32,
+        offset: u32,
+    ) -> Self {
+        Select {
+            request_id,
+            space,
+            index,
+            key,
+            iterator,
+            limit,
+            offset,
+            after: None,
+            fetch_position: false,
+        }
+    }
+
+    pub fn after(mut self, after: Option<After<'a>>) -> Self {
+        self.after = after;
+        self
+    }
+
+    /// Asks the server to return the position of the last selected tuple.
+    pub fn fetch_position(mut self, fetch_position: bool) -> Self {
+        self.fetch_position = fetch_position;
+        self
+    }
+}
+
+impl<K: Serialize, W: Write> Request<W> for Select<'_, K> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_SELECT;
+    const IDEMPOTENT: bool = true;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        let map_len = 6 + self.after.is_some() as u32 + self.fetch_position as u32;
+        encode::write_map_len(wr, map_len)?;
+
+        self.space.encode(wr)?;
+
+        self.index.encode(wr)?;
+
+        encode::write_pfix(wr, consts::IPROTO_LIMIT)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_uint(wr, self.limit as u64)?;
+
+        encode::write_pfix(wr, consts::IPROTO_OFFSET)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_uint(wr, self.offset as u64)?;
+
+        encode::write_pfix(wr, consts::IPROTO_ITERATOR)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_pfix(wr, self.iterator as u8).map_err(ValueWriteError::InvalidMarkerWrite)?;
+
+        encode::write_pfix(wr, consts::IPROTO_KEY).map_err(ValueWriteError::InvalidMarkerWrite)?;
+        rmp_serde::encode::write(wr, self.key)?;
+
+        match self.after {
+            Some(After::Position(position)) => {
+                encode::write_pfix(wr, consts::IPROTO_AFTER_POSITION)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                let position = position.as_bytes();
+                encode::write_str_len(wr, position.len() as u32)?;
+                wr.write_all(position)
+                    .map_err(ValueWriteError::InvalidDataWrite)?;
+            }
+            Some(After::Tuple(tuple)) => {
+                encode::write_pfix(wr, consts::IPROTO_AFTER_TUPLE)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                rmp_serde::encode::write(wr, tuple)?;
+            }
+            None => {}
+        }
+
+        if self.fetch_position {
+            encode::write_pfix(wr, consts::IPROTO_FETCH_POSITION)
+                .map_err(ValueWriteError::InvalidMarkerWrite)?;
+            encode::write_bool(wr, true).map_err(ValueWriteError::InvalidMarkerWrite)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Insert<'a, T: Serialize> {
+    request_id: usize,
+    space: SpaceRef<'a>,
+    tuple: &'a T,
+}
+
+impl<'a, T: Serialize> Insert<'a, T> {
+    pub fn new(request_id: usize, space: SpaceRef<'a>, tuple: &'a T) -> Self {
+        Insert {
+            request_id,
+            space,
+            tuple,
+        }
+    }
+}
+
+impl<T: Serialize, W: Write> Request<W> for Insert<'_, T> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_INSERT;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode_space_tuple(wr, self.space, self.tuple)
+    }
+}
+
+pub struct Replace<'a, T: Serialize> {
+    request_id: usize,
+    space: SpaceRef<'a>,
+    tuple: &'a T,
+}
+
+impl<'a, T: Serialize> Replace<'a, T> {
+    pub fn new(request_id: usize, space: SpaceRef<'a>, tuple: &'a T) -> Self {
+        Replace {
+            request_id,
+            space,
+            tuple,
+        }
+    }
+}
+
+impl<T: Serialize, W: Write> Request<W> for Replace<'_, T> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_REPLACE;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode_space_tuple(wr, self.space, self.tuple)
+    }
+}
+
+fn encode_space_tuple<W: Write, T: Serialize>(
+    wr: &mut W,
+    space: SpaceRef<'_>,
+    tuple: &T,
+) -> Result<(), rmp_serde::encode::Error> {
+    encode::write_map_len(wr, 2)?;
+
+    space.encode(wr)?;
+
+    encode::write_pfix(wr, consts::IPROTO_TUPLE).map_err(ValueWriteError::InvalidMarkerWrite)?;
+    rmp_serde::encode::write(wr, tuple)?;
+
+    Ok(())
+}
+
+pub struct Delete<'a, K: Serialize> {
+    request_id: usize,
+    space: SpaceRef<'a>,
+    index: IndexRef<'a>,
+    key: &'a K,
+}
+
+impl<'a, K: Serialize> Delete<'a, K> {
+    pub fn new(request_id: usize, space: SpaceRef<'a>, index: IndexRef<'a>, key: &'a K) -> Self {
+        Delete {
+            request_id,
+            space,
+            index,
+            key,
+        }
+    }
+}
+
+impl<K: Serialize, W: Write> Request<W> for Delete<'_, K> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_DELETE;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 3)?;
+
+        self.space.encode(wr)?;
+
+        self.index.encode(wr)?;
+
+        encode::write_pfix(wr, consts::IPROTO_KEY).map_err(ValueWriteError::InvalidMarkerWrite)?;
+        rmp_serde::encode::write(wr, self.key)?;
+
+        Ok(())
+    }
+}
+
+pub struct Update<'a, K: Serialize> {
+    request_id: usize,
+    space: SpaceRef<'a>,
+    index: IndexRef<'a>,
+    key: &'a K,
+    ops: &'a UpdateOps<'a>,
+}
+
+impl<'a, K: Serialize> Update<'a, K> {
+    pub fn new(
+        request_id: usize,
+        space: SpaceRef<'a>,
+        index: IndexRef<'a>,
+        key: &'a K,
+        ops: &'a UpdateOps<'a>,
+    ) -> Self {
+        Update {
+            request_id,
+            space,
+            index,
+            key,
+            ops,
+        }
+    }
+}
+
+impl<K: Serialize, W: Write> Request<W> for Update<'_, K> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_UPDATE;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 4)?;
+
+        self.space.encode(wr)?;
+
+        self.index.encode(wr)?;
+
+        encode::write_pfix(wr, consts::IPROTO_KEY).map_err(ValueWriteError::InvalidMarkerWrite)?;
+        rmp_serde::encode::write(wr, self.key)?;
+
+        // update operations are passed under the tuple key
+        encode::write_pfix(wr, consts::IPROTO_TUPLE)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        self.ops.encode(wr)?;
+
+        Ok(())
+    }
+}
+
+pub struct Upsert<'a, T: Serialize> {
+    request_id: usize,
+    space: SpaceRef<'a>,
+    tuple: &'a T,
+    ops: &'a UpdateOps<'a>,
+}
+
+impl<'a, T: Serialize> Upsert<'a, T> {
+    pub fn new(
+        request_id: usize,
+        space: SpaceRef<'a>,
+        tuple: &'a T,
+        ops: &'a UpdateOps<'a>,
+    ) -> Self {
+        Upsert {
+            request_id,
+            space,
+            tuple,
+            ops,
+        }
+    }
+}
+
+impl<T: Serialize, W: Write> Request<W> for Upsert<'_, T> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_UPSERT;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 3)?;
+
+        self.space.encode(wr)?;
+
+        encode::write_pfix(wr, consts::IPROTO_TUPLE)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        rmp_serde::encode::write(wr, self.tuple)?;
+
+        encode::write_pfix(wr, consts::IPROTO_OPS).map_err(ValueWriteError::InvalidMarkerWrite)?;
+        self.ops.encode(wr)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SqlQuery<'a> {
+    Text(&'a str),
+    Statement(u64),
+}
+
+impl SqlQuery<'_> {
+    fn encode<W: Write>(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        match self {
+            SqlQuery::Text(sql) => {
+                encode::write_pfix(wr, consts::IPROTO_SQL_TEXT)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                encode::write_str(wr, sql)?;
+            }
+            SqlQuery::Statement(stmt_id) => {
+                encode::write_pfix(wr, consts::IPROTO_STMT_ID)
+                    .map_err(ValueWriteError::InvalidMarkerWrite)?;
+                encode::write_uint(wr, *stmt_id)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Execute<'a, T: Serialize> {
+    request_id: usize,
+    query: SqlQuery<'a>,
+    binds: &'a T,
+}
+
+impl<'a, T: Serialize> Execute<'a, T> {
+    pub fn new(request_id: usize, query: SqlQuery<'a>, binds: &'a T) -> Self {
+        Execute {
+            request_id,
+            query,
+            binds,
+        }
+    }
+}
+
+impl<T: Serialize, W: Write> Request<W> for Execute<'_, T> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_EXECUTE;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 3)?;
+
+        self.query.encode(wr)?;
+
+        encode::write_pfix(wr, consts::IPROTO_SQL_BIND)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        rmp_serde::encode::write(wr, self.binds)?;
+
+        encode::write_pfix(wr, consts::IPROTO_OPTIONS)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_array_len(wr, 0)?;
+
+        Ok(())
+    }
+}
+
+/// Prepares SQL text, or unprepares a statement when built from a statement id.
+pub struct Prepare<'a> {
+    request_id: usize,
+    query: SqlQuery<'a>,
+}
+
+impl<'a> Prepare<'a> {
+    pub fn new(request_id: usize, query: SqlQuery<'a>) -> Self {
+        Prepare { request_id, query }
+    }
+}
+
+impl<W: Write> Request<W> for Prepare<'_> {
+    const REQUEST_TYPE: u8 = consts::IPROTO_PREPARE;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 1)?;
+        self.query.encode(wr)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum TxnIsolation {
+    #[default]
+    Default = 0,
+    ReadCommitted = 1,
+    ReadConfirmed = 2,
+    BestEffort = 3,
+}
+
+pub struct Begin {
+    request_id: usize,
+    isolation: TxnIsolation,
+    timeout: Option<Duration>,
+}
+
+impl Begin {
+    pub fn new(request_id: usize, isolation: TxnIsolation, timeout: Option<Duration>) -> Self {
+        Begin {
+            request_id,
+            isolation,
+            timeout,
+        }
+    }
+}
+
+impl<W: Write> Request<W> for Begin {
+    const REQUEST_TYPE: u8 = consts::IPROTO_BEGIN;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, if self.timeout.is_some() { 2 } else { 1 })?;
+
+        encode::write_pfix(wr, consts::IPROTO_TXN_ISOLATION)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_pfix(wr, self.isolation as u8)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+
+        if let Some(timeout) = self.timeout {
+            encode::write_pfix(wr, consts::IPROTO_TIMEOUT)
+                .map_err(ValueWriteError::InvalidMarkerWrite)?;
+            encode::write_f64(wr, timeout.as_secs_f64())?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Commit {
+    request_id: usize,
+}
+
+impl Commit {
+    pub fn new(request_id: usize) -> Self {
+        Commit { request_id }
+    }
+}
+
+impl<W: Write> Request<W> for Commit {
+    const REQUEST_TYPE: u8 = consts::IPROTO_COMMIT;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 0)?;
+        Ok(())
+    }
+}
+
+pub struct Rollback {
+    request_id: usize,
+}
+
+impl Rollback {
+    pub fn new(request_id: usize) -> Self {
+        Rollback { request_id }
+    }
+}
+
+impl<W: Write> Request<W> for Rollback {
+    const REQUEST_TYPE: u8 = consts::IPROTO_ROLLBACK;
+
+    fn request_id(&self) -> usize {
+        self.request_id
+    }
+
+    fn encode_body(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_map_len(wr, 0)?;
+        Ok(())
+    }
+}
+
 pub struct Eval<'a, T: Serialize> {
     request_id: usize,
     expression: &'a str,
     args: &'a T,
 }
 
+impl<'a, T: Serialize> Eval<'a, T> {
+    pub fn new(request_id: usize, expression: &'a str, args: &'a T) -> Self {
+        Eval {
+            request_id,
+            expression,
+            args,
+        }
+    }
+}
+
 impl<T: Serialize, W: Write> Request<W> for Eval<'_, T> {
-    const REQUEST_TYPE: u8 = consts::IPROTO_CALL;
+    const REQUEST_TYPE: u8 = consts::IPROTO_EVAL;
 
     fn request_id(&self) -> usize {
         self.request_id
@@ -115,9 +940,44 @@ impl<T: Serialize, W: Write> Request<W> for Eval<'_, T> {
     }
 }
 
+/// How the password is proven to the server, see `box.cfg.auth_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AuthMethod {
+    /// salted SHA-1 scramble, the password itself is never sent
+    #[default]
+    ChapSha1,
+    /// the password is sent as is, so it must only be used over an encrypted connection;
+    /// chosen automatically over TLS only
+    PapSha256,
+}
+
+impl AuthMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chap-sha1" => Some(Self::ChapSha1),
+            "pap-sha256" => Some(Self::PapSha256),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ChapSha1 => "chap-sha1",
+            Self::PapSha256 => "pap-sha256",
+        }
+    }
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 pub struct Auth<'a> {
     request_id: usize,
 
+    method: AuthMethod,
     salt: &'a [u8],
     username: &'a str,
     password: Option<&'a str>,
@@ -126,12 +986,14 @@ pub struct Auth<'a> {
 impl<'a> Auth<'a> {
     pub fn new(
         request_id: usize,
+        method: AuthMethod,
         salt: &'a [u8],
         username: &'a str,
         password: Option<&'a str>,
     ) -> Self {
         Auth {
             request_id,
+            method,
             salt,
             username,
             password,
@@ -147,7 +1009,7 @@ impl<W: Write> Request<W> for Auth<'_> {
     }
 
     fn encode_body(&self, wr: &mut W) -> Result<(), Error> {
-        let scramble = make_scramble(self.salt, self.password.unwrap_or(""));
+        let password = self.password.unwrap_or("");
 
         encode::write_map_len(wr, 2)?;
 
@@ -160,10 +1022,16 @@ impl<W: Write> Request<W> for Auth<'_> {
         {
             encode::write_array_len(wr, 2)?;
 
-            encode::write_str(wr, "chap-sha1")?;
-            encode::write_str_len(wr, SCRAMBLE_SIZE as u32)?;
-            wr.write_all(&scramble)
-                .map_err(ValueWriteError::InvalidDataWrite)?;
+            encode::write_str(wr, self.method.name())?;
+            match self.method {
+                AuthMethod::ChapSha1 => {
+                    let scramble = make_scramble(self.salt, password);
+                    encode::write_str_len(wr, SCRAMBLE_SIZE as u32)?;
+                    wr.write_all(&scramble)
+                        .map_err(ValueWriteError::InvalidDataWrite)?;
+                }
+                AuthMethod::PapSha256 => encode::write_str(wr, password)?,
+            }
         }
 
         Ok(())
@@ -1,19 +1,47 @@
 use crate::iproto::consts;
-use rmp_serde::decode::Error;
-use serde::de::DeserializeOwned;
+use crate::iproto::error_code::TarantoolErrorCode;
+use crate::iproto::request::Feature;
+use rmp::decode::{NumValueReadError, ValueReadError};
+use rmp::encode::ValueWriteError;
+use serde::de::{DeserializeOwned, IgnoredAny};
 use std::collections::HashMap;
-use std::io::Read;
+use std::fmt;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error(transparent)]
+    Decode(#[from] rmp_serde::decode::Error),
+    #[error("missing key {0:#04x}")]
+    MissingKey(u8),
+}
+
+impl From<ValueReadError> for DecodeError {
+    fn from(err: ValueReadError) -> Self {
+        Self::Decode(err.into())
+    }
+}
+
+impl From<NumValueReadError> for DecodeError {
+    fn from(err: NumValueReadError) -> Self {
+        Self::Decode(err.into())
+    }
+}
 
 #[derive(Debug)]
 pub struct ResponseHeader {
     pub request_id: usize,
     pub response_code_indicator: u32,
+    pub schema_version: Option<u64>,
 }
 
 impl ResponseHeader {
-    pub fn decode<R: Read>(reader: &mut R) -> Result<Self, rmp_serde::decode::Error> {
+    pub fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
         let mut request_id: Option<usize> = None;
         let mut response_code: Option<u32> = None;
+        let mut schema_version: Option<u64> = None;
 
         let map_len = rmp::decode::read_map_len(reader)?;
         for _ in 0..map_len {
@@ -23,20 +51,23 @@ impl ResponseHeader {
                     response_code = Some(rmp::decode::read_int(reader)?);
                 }
                 consts::IPROTO_SYNC => {
-                    request_id = Some(rmp::decode::read_u64(reader)? as usize);
+                    request_id = Some(rmp::decode::read_int(reader)?);
                 }
                 consts::IPROTO_SCHEMA_VERSION => {
-                    let _: u64 = rmp::decode::read_int(reader)?;
+                    schema_version = Some(rmp::decode::read_int(reader)?);
                 }
                 _ => {
-                    panic!("invalid code");
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
                 }
             }
         }
 
         Ok(ResponseHeader {
-            request_id: request_id.unwrap(),
-            response_code_indicator: response_code.unwrap(),
+            // events are not bound to any request
+            request_id: request_id.unwrap_or(0),
+            response_code_indicator: response_code
+                .ok_or(DecodeError::MissingKey(consts::RESPONSE_CODE_INDICATOR))?,
+            schema_version,
         })
     }
 
@@ -49,18 +80,7 @@ impl ResponseHeader {
 }
 
 pub trait ResponseBody: Sized {
-    fn decode<R: Read>(reader: &mut R) -> Result<Self, rmp_serde::decode::Error>;
-}
-
-pub struct Response<B: ResponseBody> {
-    header: ResponseHeader,
-    body: B,
-}
-
-impl<B: ResponseBody> Response<B> {
-    pub fn from_parts(header: ResponseHeader, body: B) -> Self {
-        Self { header, body }
-    }
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError>;
 }
 
 pub struct CallResponse<D: DeserializeOwned> {
@@ -74,7 +94,7 @@ impl<D: DeserializeOwned> CallResponse<D> {
 }
 
 impl<D: DeserializeOwned> ResponseBody for CallResponse<D> {
-    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
         let mut data: Option<D> = None;
 
         let map_len = rmp::decode::read_map_len(reader)?;
@@ -85,18 +105,18 @@ impl<D: DeserializeOwned> ResponseBody for CallResponse<D> {
                     data = Some(rmp_serde::decode::from_read(reader.by_ref())?);
                 }
                 _ => {
-                    let _: () = rmp_serde::decode::from_read(reader.by_ref())?;
-                    panic!("invalid op");
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
                 }
             }
         }
 
         Ok(Self {
-            data: data.unwrap(),
+            data: data.ok_or(DecodeError::MissingKey(consts::IPROTO_DATA))?,
         })
     }
 }
 
+/// Entry of the MP_ERROR_STACK extension, the source of an entry is the next one in the stack.
 #[derive(Debug, Clone)]
 pub struct ErrorExtra {
     pub error_type: String,
@@ -106,98 +126,526 @@ pub struct ErrorExtra {
     pub errno: u64,
     pub errcode: u64,
     pub error_fields: Option<HashMap<String, rmpv::Value>>,
+
+    /// fields unknown to this version of the crate
+    pub unknown_fields: HashMap<u8, rmpv::Value>,
+
+    /// error this one was caused by (`prev` in `box.error`)
+    prev: Option<Arc<ErrorExtra>>,
+}
+
+impl ErrorExtra {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        use rmp_serde::decode;
+
+        let mut error_type: Option<String> = None;
+        let mut error_file = String::new();
+        let mut error_line = 0;
+        let mut error_message: Option<String> = None;
+        let mut errno = 0;
+        let mut errcode = 0;
+        let mut error_fields: Option<HashMap<String, rmpv::Value>> = None;
+        let mut unknown_fields = HashMap::new();
+
+        let fields_n = rmp::decode::read_map_len(reader)?;
+        for _ in 0..fields_n {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::MP_ERROR_TYPE => {
+                    error_type = Some(decode::from_read(reader.by_ref())?);
+                }
+                consts::MP_ERROR_FILE => {
+                    error_file = decode::from_read(reader.by_ref())?;
+                }
+                consts::MP_ERROR_LINE => {
+                    error_line = rmp::decode::read_int(reader)?;
+                }
+                consts::MP_ERROR_MESSAGE => {
+                    error_message = Some(decode::from_read(reader.by_ref())?);
+                }
+                consts::MP_ERROR_ERRNO => {
+                    errno = rmp::decode::read_int(reader)?;
+                }
+                consts::MP_ERROR_ERRCODE => {
+                    errcode = rmp::decode::read_int(reader)?;
+                }
+                consts::MP_ERROR_FIELDS => {
+                    error_fields = Some(decode::from_read(reader.by_ref())?);
+                }
+                _ => {
+                    unknown_fields.insert(code, decode::from_read(reader.by_ref())?);
+                }
+            }
+        }
+
+        Ok(Self {
+            error_type: error_type.ok_or(DecodeError::MissingKey(consts::MP_ERROR_TYPE))?,
+            error_file,
+            error_line,
+            error_message: error_message
+                .ok_or(DecodeError::MissingKey(consts::MP_ERROR_MESSAGE))?,
+            errno,
+            errcode,
+            error_fields,
+            unknown_fields,
+            prev: None,
+        })
+    }
+
+    /// Decodes the MP_ERROR extension map, linking every entry to the next one.
+    pub(crate) fn decode_stack<R: Read>(reader: &mut R) -> Result<Vec<Self>, DecodeError> {
+        let mut stack = Vec::new();
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let key = rmp::decode::read_pfix(reader)?;
+            match key {
+                consts::MP_ERROR_STACK => {
+                    let stack_len = rmp::decode::read_array_len(reader)?;
+                    for _ in 0..stack_len {
+                        stack.push(Self::decode(reader)?);
+                    }
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        let mut prev: Option<Arc<Self>> = None;
+        for extra in stack.iter_mut().rev() {
+            extra.prev = prev.clone();
+            prev = Some(Arc::new(extra.clone()));
+        }
+
+        Ok(stack)
+    }
+}
+
+impl ErrorExtra {
+    /// Encodes the stack as the MP_ERROR extension map.
+    pub(crate) fn encode_stack<W: Write>(
+        stack: &[Self],
+        wr: &mut W,
+    ) -> Result<(), rmp_serde::encode::Error> {
+        use rmp::encode;
+
+        encode::write_map_len(wr, 1)?;
+        encode::write_pfix(wr, consts::MP_ERROR_STACK)
+            .map_err(ValueWriteError::InvalidMarkerWrite)?;
+        encode::write_array_len(wr, stack.len() as u32)?;
+
+        for extra in stack {
+            let fields_n =
+                6 + extra.error_fields.is_some() as u32 + extra.unknown_fields.len() as u32;
+            encode::write_map_len(wr, fields_n)?;
+
+            let write_key = |wr: &mut W, key| {
+                encode::write_pfix(wr, key).map_err(ValueWriteError::InvalidMarkerWrite)
+            };
+            write_key(wr, consts::MP_ERROR_TYPE)?;
+            encode::write_str(wr, &extra.error_type)?;
+            write_key(wr, consts::MP_ERROR_FILE)?;
+            encode::write_str(wr, &extra.error_file)?;
+            write_key(wr, consts::MP_ERROR_LINE)?;
+            encode::write_uint(wr, extra.error_line)?;
+            write_key(wr, consts::MP_ERROR_MESSAGE)?;
+            encode::write_str(wr, &extra.error_message)?;
+            write_key(wr, consts::MP_ERROR_ERRNO)?;
+            encode::write_uint(wr, extra.errno)?;
+            write_key(wr, consts::MP_ERROR_ERRCODE)?;
+            encode::write_uint(wr, extra.errcode)?;
+            if let Some(error_fields) = &extra.error_fields {
+                write_key(wr, consts::MP_ERROR_FIELDS)?;
+                rmp_serde::encode::write(wr, error_fields)?;
+            }
+            for (key, value) in &extra.unknown_fields {
+                encode::write_uint(wr, *key as u64)?;
+                rmpv::encode::write_value(wr, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ErrorExtra {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.error_type, self.error_message)
+    }
+}
+
+impl std::error::Error for ErrorExtra {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.prev.as_deref().map(|prev| prev as _)
+    }
 }
 
+/// Error returned by the server. `stack` holds the whole error chain starting from
+/// this error, servers older than 2.4.1 only send the message.
 #[derive(Debug, Clone)]
 pub struct ErrorResponse {
+    /// taken from the response code, the body does not repeat it
+    pub code: TarantoolErrorCode,
     pub error: String,
-    pub error_extra: Option<ErrorExtra>,
+    pub stack: Vec<ErrorExtra>,
 }
 
-impl ResponseBody for ErrorResponse {
-    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
-        use rmp_serde::decode;
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.error)
+    }
+}
+
+impl std::error::Error for ErrorResponse {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // the first entry describes this error itself
+        self.stack.first()?.source()
+    }
+}
 
+impl ResponseBody for ErrorResponse {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
         let mut err: Option<String> = None;
-        let mut error_extra: Option<ErrorExtra> = None;
+        let mut stack = Vec::new();
 
         let map_len = rmp::decode::read_map_len(reader)?;
         for _ in 0..map_len {
             let code = rmp::decode::read_pfix(reader)?;
             match code {
                 consts::IPROTO_ERROR_24 => {
-                    err = Some(decode::from_read(reader.by_ref())?);
+                    err = Some(rmp_serde::decode::from_read(reader.by_ref())?);
                 }
                 consts::IPROTO_ERROR => {
-                    let error_extra_map_len = rmp::decode::read_map_len(reader)?;
-                    assert_eq!(error_extra_map_len, 1);
-
-                    let key = rmp::decode::read_pfix(reader)?;
-                    assert_eq!(key, consts::MP_ERROR_STACK);
-
-                    let error_stack_len = rmp::decode::read_array_len(reader)?;
-                    assert_eq!(error_stack_len, 1);
-
-                    let mut error_type: Option<String> = None;
-                    let mut error_file: Option<String> = None;
-                    let mut error_line: Option<u64> = None;
-                    let mut error_message: Option<String> = None;
-                    let mut errno: Option<u64> = None;
-                    let mut errcode: Option<u64> = None;
-                    let mut error_fields: Option<HashMap<String, rmpv::Value>> = None;
-
-                    let fields_n = rmp::decode::read_map_len(reader)?;
-                    for _ in 0..fields_n {
-                        let code = rmp::decode::read_pfix(reader)?;
-                        match code {
-                            consts::MP_ERROR_TYPE => {
-                                error_type = Some(rmp_serde::decode::from_read(reader.by_ref())?);
-                            }
-                            consts::MP_ERROR_FILE => {
-                                error_file = Some(rmp_serde::decode::from_read(reader.by_ref())?);
-                            }
-                            consts::MP_ERROR_LINE => {
-                                error_line = Some(rmp::decode::read_int(reader)?);
-                            }
-                            consts::MP_ERROR_MESSAGE => {
-                                error_message =
-                                    Some(rmp_serde::decode::from_read(reader.by_ref())?);
-                            }
-                            consts::MP_ERROR_ERRNO => {
-                                errno = Some(rmp::decode::read_int(reader)?);
-                            }
-                            consts::MP_ERROR_ERRCODE => {
-                                errcode = Some(rmp::decode::read_int(reader)?);
-                            }
-                            consts::MP_ERROR_FIELDS => {
-                                error_fields = Some(rmp_serde::decode::from_read(reader.by_ref())?);
-                            }
-                            _ => {
-                                println!("invalid code: {}", code);
-                            }
-                        }
-                    }
+                    stack = ErrorExtra::decode_stack(reader)?;
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        let error = match err {
+            Some(err) => err,
+            None => stack
+                .first()
+                .map(|extra| extra.error_message.clone())
+                .ok_or(DecodeError::MissingKey(consts::IPROTO_ERROR_24))?,
+        };
+
+        Ok(Self {
+            code: TarantoolErrorCode::Unknown,
+            error,
+            stack,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMetadata {
+    pub name: String,
+    pub field_type: String,
+    pub collation: Option<String>,
+    pub is_nullable: Option<bool>,
+    pub is_autoincrement: Option<bool>,
+    pub span: Option<String>,
+}
+
+impl ColumnMetadata {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        use rmp_serde::decode;
+
+        let mut name: Option<String> = None;
+        let mut field_type: Option<String> = None;
+        let mut collation: Option<String> = None;
+        let mut is_nullable: Option<bool> = None;
+        let mut is_autoincrement: Option<bool> = None;
+        let mut span: Option<String> = None;
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::IPROTO_FIELD_NAME => {
+                    name = Some(decode::from_read(reader.by_ref())?);
+                }
+                consts::IPROTO_FIELD_TYPE => {
+                    field_type = Some(decode::from_read(reader.by_ref())?);
+                }
+                consts::IPROTO_FIELD_COLL => {
+                    collation = Some(decode::from_read(reader.by_ref())?);
+                }
+                consts::IPROTO_FIELD_IS_NULLABLE => {
+                    is_nullable = Some(rmp::decode::read_bool(reader)?);
+                }
+                consts::IPROTO_FIELD_IS_AUTOINCREMENT => {
+                    is_autoincrement = Some(rmp::decode::read_bool(reader)?);
+                }
+                consts::IPROTO_FIELD_SPAN => {
+                    span = decode::from_read(reader.by_ref())?;
+                }
+                _ => {
+                    let _: IgnoredAny = decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.unwrap_or_default(),
+            field_type: field_type.unwrap_or_default(),
+            collation,
+            is_nullable,
+            is_autoincrement,
+            span,
+        })
+    }
+
+    fn decode_list<R: Read>(reader: &mut R) -> Result<Vec<Self>, DecodeError> {
+        let len = rmp::decode::read_array_len(reader)?;
+        (0..len).map(|_| Self::decode(reader)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SqlInfo {
+    pub row_count: u64,
+    pub autoincrement_ids: Vec<i64>,
+}
+
+impl SqlInfo {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut info = SqlInfo::default();
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::SQL_INFO_ROW_COUNT => {
+                    info.row_count = rmp::decode::read_int(reader)?;
+                }
+                consts::SQL_INFO_AUTOINCREMENT_IDS => {
+                    info.autoincrement_ids = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+/// Result of an SQL statement: rows and their metadata for queries,
+/// `info` for data-changing statements.
+#[derive(Debug, Clone)]
+pub struct SqlResponse<T: DeserializeOwned> {
+    pub metadata: Vec<ColumnMetadata>,
+    pub rows: Vec<T>,
+    pub info: Option<SqlInfo>,
+}
+
+impl<T: DeserializeOwned> ResponseBody for SqlResponse<T> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut metadata = Vec::new();
+        let mut rows = Vec::new();
+        let mut info = None;
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::IPROTO_METADATA => {
+                    metadata = ColumnMetadata::decode_list(reader)?;
+                }
+                consts::IPROTO_DATA => {
+                    rows = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+                consts::IPROTO_SQL_INFO => {
+                    info = Some(SqlInfo::decode(reader)?);
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        Ok(Self {
+            metadata,
+            rows,
+            info,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrepareResponse {
+    pub stmt_id: u64,
+    pub bind_count: u64,
+    pub bind_metadata: Vec<ColumnMetadata>,
+    pub metadata: Vec<ColumnMetadata>,
+}
+
+impl ResponseBody for PrepareResponse {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut stmt_id: Option<u64> = None;
+        let mut bind_count = 0;
+        let mut bind_metadata = Vec::new();
+        let mut metadata = Vec::new();
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::IPROTO_STMT_ID => {
+                    stmt_id = Some(rmp::decode::read_int(reader)?);
+                }
+                consts::IPROTO_BIND_COUNT => {
+                    bind_count = rmp::decode::read_int(reader)?;
+                }
+                consts::IPROTO_BIND_METADATA => {
+                    bind_metadata = ColumnMetadata::decode_list(reader)?;
+                }
+                consts::IPROTO_METADATA => {
+                    metadata = ColumnMetadata::decode_list(reader)?;
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        Ok(Self {
+            stmt_id: stmt_id.ok_or(DecodeError::MissingKey(consts::IPROTO_STMT_ID))?,
+            bind_count,
+            bind_metadata,
+            metadata,
+        })
+    }
+}
+
+/// Protocol version and features negotiated with IPROTO_ID.
+/// Servers older than 2.10 do not support IPROTO_ID and report no features.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerFeatures {
+    pub protocol_version: u64,
+    pub features: Vec<Feature>,
+    pub auth_type: Option<String>,
+}
 
-                    error_extra = Some(ErrorExtra {
-                        error_type: error_type.unwrap(),
-                        error_file: error_file.unwrap(),
-                        error_line: error_line.unwrap(),
-                        error_message: error_message.unwrap(),
-                        errno: errno.unwrap(),
-                        errcode: errcode.unwrap(),
-                        error_fields,
-                    });
+impl ServerFeatures {
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+impl ResponseBody for ServerFeatures {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut server_features = ServerFeatures::default();
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::IPROTO_VERSION => {
+                    server_features.protocol_version = rmp::decode::read_int(reader)?;
+                }
+                consts::IPROTO_FEATURES => {
+                    let features: Vec<u64> = rmp_serde::decode::from_read(reader.by_ref())?;
+                    server_features.features =
+                        features.into_iter().filter_map(Feature::from_id).collect();
+                }
+                consts::IPROTO_AUTH_TYPE => {
+                    server_features.auth_type =
+                        Some(rmp_serde::decode::from_read(reader.by_ref())?);
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        Ok(server_features)
+    }
+}
+
+/// Body of an IPROTO_EVENT packet sent by the server for a watched key.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub key: String,
+    pub data: rmpv::Value,
+}
+
+impl ResponseBody for Event {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut key: Option<String> = None;
+        let mut data = rmpv::Value::Nil;
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::IPROTO_EVENT_KEY => {
+                    key = Some(rmp_serde::decode::from_read(reader.by_ref())?);
+                }
+                consts::IPROTO_EVENT_DATA => {
+                    data = rmp_serde::decode::from_read(reader.by_ref())?;
                 }
                 _ => {
-                    let _: () = decode::from_read(reader.by_ref())?;
-                    panic!("invalid op");
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
                 }
             }
         }
 
         Ok(Self {
-            error: err.unwrap(),
-            error_extra,
+            key: key.ok_or(DecodeError::MissingKey(consts::IPROTO_EVENT_KEY))?,
+            data,
+        })
+    }
+}
+
+/// Opaque position of a tuple in an index, pagination continues after it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Position(Vec<u8>);
+
+impl Position {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Response to a select with the position of its last tuple.
+pub struct SelectResponse<T: DeserializeOwned> {
+    pub tuples: Vec<T>,
+    pub position: Option<Position>,
+}
+
+impl<T: DeserializeOwned> ResponseBody for SelectResponse<T> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut tuples: Option<Vec<T>> = None;
+        let mut position = None;
+
+        let map_len = rmp::decode::read_map_len(reader)?;
+        for _ in 0..map_len {
+            let code = rmp::decode::read_pfix(reader)?;
+            match code {
+                consts::IPROTO_DATA => {
+                    tuples = Some(rmp_serde::decode::from_read(reader.by_ref())?);
+                }
+                consts::IPROTO_POSITION => {
+                    // a string, but not necessarily a valid UTF-8 one
+                    let raw: serde_bytes::ByteBuf = rmp_serde::decode::from_read(reader.by_ref())?;
+                    position = Some(Position(raw.into_vec()));
+                }
+                _ => {
+                    let _: IgnoredAny = rmp_serde::decode::from_read(reader.by_ref())?;
+                }
+            }
+        }
+
+        Ok(Self {
+            tuples: tuples.ok_or(DecodeError::MissingKey(consts::IPROTO_DATA))?,
+            position,
         })
     }
 }
@@ -205,7 +653,51 @@ impl ResponseBody for ErrorResponse {
 pub struct EmptyResponse;
 
 impl ResponseBody for EmptyResponse {
-    fn decode<R: Read>(_reader: &mut R) -> Result<Self, Error> {
+    fn decode<R: Read>(_reader: &mut R) -> Result<Self, DecodeError> {
         Ok(Self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorResponse, ResponseBody};
+    use crate::iproto::consts;
+    use rmpv::Value;
+    use std::error::Error;
+
+    #[test]
+    fn error_stack_test() {
+        let extra = |message: &str, code: u64| {
+            Value::Map(vec![
+                (consts::MP_ERROR_TYPE.into(), "ClientError".into()),
+                (consts::MP_ERROR_MESSAGE.into(), message.into()),
+                (consts::MP_ERROR_ERRCODE.into(), code.into()),
+                (0x7F.into(), "future field".into()),
+            ])
+        };
+        let stack = Value::Array(vec![extra("outer", 32), extra("inner", 3)]);
+        let body = Value::Map(vec![
+            (consts::IPROTO_ERROR_24.into(), "outer".into()),
+            (
+                consts::IPROTO_ERROR.into(),
+                Value::Map(vec![(consts::MP_ERROR_STACK.into(), stack)]),
+            ),
+        ]);
+
+        let mut buf = Vec::new();
+        rmpv::encode::write_value(&mut buf, &body).unwrap();
+        let err = ErrorResponse::decode(&mut buf.as_slice()).unwrap();
+
+        assert_eq!(err.error, "outer");
+        assert_eq!(err.stack.len(), 2);
+        assert_eq!(err.stack[1].errcode, 3);
+        assert_eq!(
+            err.stack[0].unknown_fields[&0x7F],
+            Value::from("future field")
+        );
+
+        let source = err.source().unwrap();
+        assert_eq!(source.to_string(), "ClientError: inner");
+        assert!(source.source().is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use rmp::encode;
+use std::io::Write;
+
+/// Field addressed by an update operation: a zero-based field number
+/// (negative numbers count from the end of the tuple) or a JSON path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field<'a> {
+    Number(i32),
+    Path(&'a str),
+}
+
+impl From<i32> for Field<'_> {
+    fn from(number: i32) -> Self {
+        Field::Number(number)
+    }
+}
+
+impl<'a> From<&'a str> for Field<'a> {
+    fn from(path: &'a str) -> Self {
+        Field::Path(path)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Splice,
+    Insert,
+    Delete,
+    Assign,
+}
+
+impl Operator {
+    fn as_str(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::And => "&",
+            Operator::Or => "|",
+            Operator::Xor => "^",
+            Operator::Splice => ":",
+            Operator::Insert => "!",
+            Operator::Delete => "#",
+            Operator::Assign => "=",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Args<'a> {
+    Value(rmpv::Value),
+    Uint(u64),
+    Splice {
+        position: i32,
+        length: u32,
+        replacement: &'a str,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Operation<'a> {
+    operator: Operator,
+    field: Field<'a>,
+    args: Args<'a>,
+}
+
+/// List of operations for UPDATE and UPSERT requests.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateOps<'a> {
+    ops: Vec<Operation<'a>>,
+}
+
+impl<'a> UpdateOps<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, operator: Operator, field: impl Into<Field<'a>>, args: Args<'a>) -> Self {
+        self.ops.push(Operation {
+            operator,
+            field: field.into(),
+            args,
+        });
+        self
+    }
+
+    /// `+`: adds `value` to a numeric field.
+    pub fn add(self, field: impl Into<Field<'a>>, value: impl Into<rmpv::Value>) -> Self {
+        self.push(Operator::Add, field, Args::Value(value.into()))
+    }
+
+    /// `-`: subtracts `value` from a numeric field.
+    pub fn sub(self, field: impl Into<Field<'a>>, value: impl Into<rmpv::Value>) -> Self {
+        self.push(Operator::Sub, field, Args::Value(value.into()))
+    }
+
+    /// `&`: bitwise AND of an unsigned field and `value`.
+    pub fn bit_and(self, field: impl Into<Field<'a>>, value: u64) -> Self {
+        self.push(Operator::And, field, Args::Uint(value))
+    }
+
+    /// `|`: bitwise OR of an unsigned field and `value`.
+    pub fn bit_or(self, field: impl Into<Field<'a>>, value: u64) -> Self {
+        self.push(Operator::Or, field, Args::Uint(value))
+    }
+
+    /// `^`: bitwise XOR of an unsigned field and `value`.
+    pub fn bit_xor(self, field: impl Into<Field<'a>>, value: u64) -> Self {
+        self.push(Operator::Xor, field, Args::Uint(value))
+    }
+
+    /// `:`: replaces `length` characters of a string field starting at
+    /// `position` (1-based, negative counts from the end) with `replacement`.
+    pub fn splice(
+        self,
+        field: impl Into<Field<'a>>,
+        position: i32,
+        length: u32,
+        replacement: &'a str,
+    ) -> Self {
+        let args = Args::Splice {
+            position,
+            length,
+            replacement,
+        };
+        self.push(Operator::Splice, field, args)
+    }
+
+    /// `!`: inserts `value` before the field.
+    pub fn insert(self, field: impl Into<Field<'a>>, value: impl Into<rmpv::Value>) -> Self {
+        self.push(Operator::Insert, field, Args::Value(value.into()))
+    }
+
+    /// `#`: deletes `count` fields starting from the field.
+    pub fn delete(self, field: impl Into<Field<'a>>, count: u32) -> Self {
+        self.push(Operator::Delete, field, Args::Uint(count as u64))
+    }
+
+    /// `=`: assigns `value` to the field.
+    pub fn assign(self, field: impl Into<Field<'a>>, value: impl Into<rmpv::Value>) -> Self {
+        self.push(Operator::Assign, field, Args::Value(value.into()))
+    }
+
+    pub(crate) fn encode<W: Write>(&self, wr: &mut W) -> Result<(), rmp_serde::encode::Error> {
+        encode::write_array_len(wr, self.ops.len() as u32)?;
+
+        for op in &self.ops {
+            let args_len = match op.args {
+                Args::Splice { .. } => 3,
+                _ => 1,
+            };
+            encode::write_array_len(wr, 2 + args_len)?;
+
+            encode::write_str(wr, op.operator.as_str())?;
+            match op.field {
+                Field::Number(number) => {
+                    encode::write_sint(wr, number as i64)?;
+                }
+                Field::Path(path) => {
+                    encode::write_str(wr, path)?;
+                }
+            }
+
+            match &op.args {
+                Args::Value(value) => {
+                    rmpv::encode::write_value(wr, value)?;
+                }
+                Args::Uint(value) => {
+                    encode::write_uint(wr, *value)?;
+                }
+                Args::Splice {
+                    position,
+                    length,
+                    replacement,
+                } => {
+                    encode::write_sint(wr, *position as i64)?;
+                    encode::write_uint(wr, *length as u64)?;
+                    encode::write_str(wr, replacement)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UpdateOps;
+    use rmpv::Value;
+
+    #[test]
+    fn encode_test() {
+        let ops = UpdateOps::new()
+            .add(1, 10)
+            .bit_or(2, 0b100)
+            .splice("name", 1, 2, "ab")
+            .delete(-1, 1)
+            .assign(3, "x");
+
+        let mut buf = Vec::new();
+        ops.encode(&mut buf).unwrap();
+
+        let op = Value::Array;
+        let value = rmpv::decode::read_value(&mut buf.as_slice()).unwrap();
+        let expected = Value::Array(vec![
+            op(vec!["+".into(), 1.into(), 10.into()]),
+            op(vec!["|".into(), 2.into(), 4.into()]),
+            op(vec![
+                ":".into(),
+                "name".into(),
+                1.into(),
+                2.into(),
+                "ab".into(),
+            ]),
+            op(vec!["#".into(), (-1).into(), 1.into()]),
+            op(vec!["=".into(), 3.into(), "x".into()]),
+        ]);
+        assert_eq!(value, expected);
+    }
+}
@@ -1,3 +1,13 @@
+mod builder;
 pub mod client;
 mod iproto;
+mod pool;
+mod reconnect;
+mod schema;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "tls")]
+mod tls;
+mod transport;
+pub mod types;
 mod utils;
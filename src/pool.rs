@@ -0,0 +1,498 @@
+//! Connections to one or many instances of a replica set with load balancing between them.
+
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::task::JoinHandle;
+
+use crate::builder::ConnectionBuilder;
+use crate::client::{Connection, Error, Feature, WatchStream};
+use crate::utils::random_u64;
+
+/// How [`Pool::get`] picks a connection among the ones matching the mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    #[default]
+    RoundRobin,
+    /// the connection with the fewest requests waiting for a response
+    LeastPending,
+    Random,
+}
+
+/// Kind of instance a request should go to, judged by `box.status` of every instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// writable instances only
+    ReadWrite,
+    /// read-only instances only
+    ReadOnly,
+    /// read-only instances, any healthy instance if none is available
+    PreferReplica,
+}
+
+/// Set of connections balanced by a [`Strategy`] and checked by periodic pings.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use iproto::client::{Connection, Mode, Pool, Strategy};
+///
+/// let pool = Pool::builder()
+///     .instance(Connection::builder("master:3301").credentials("user", Some("secret")))
+///     .instance(Connection::builder("replica:3301").credentials("user", Some("secret")))
+///     .strategy(Strategy::LeastPending)
+///     .connect()
+///     .await?;
+///
+/// pool.get(Mode::ReadWrite)?.ping().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Pool {
+    instances: Vec<Arc<Instance>>,
+    strategy: Strategy,
+    next: AtomicUsize,
+    monitors: Vec<JoinHandle<()>>,
+}
+
+impl Pool {
+    pub fn builder() -> PoolBuilder {
+        PoolBuilder::default()
+    }
+
+    /// Connects to every URI once with the default settings.
+    pub async fn connect<I>(uris: I) -> io::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        uris.into_iter()
+            .fold(Self::builder(), |builder, uri| {
+                builder.instance(Connection::builder(uri))
+            })
+            .connect()
+            .await
+    }
+
+    /// Picks a healthy connection for the mode.
+    pub fn get(&self, mode: Mode) -> Result<Arc<Connection>, Error> {
+        let healthy = || {
+            self.instances
+                .iter()
+                .filter(|instance| instance.is_healthy())
+        };
+        let with_role = |role| healthy().filter(move |instance| instance.role() == role);
+
+        let candidates: Vec<&Arc<Instance>> = match mode {
+            Mode::ReadWrite => with_role(ROLE_RW).collect(),
+            Mode::ReadOnly => with_role(ROLE_RO).collect(),
+            Mode::PreferReplica => {
+                let replicas: Vec<_> = with_role(ROLE_RO).collect();
+                if replicas.is_empty() {
+                    healthy().collect()
+                } else {
+                    replicas
+                }
+            }
+        };
+        if candidates.is_empty() {
+            return Err(Error::NoAvailableInstance(mode));
+        }
+
+        let instance = match self.strategy {
+            Strategy::RoundRobin => {
+                candidates[self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()]
+            }
+            Strategy::LeastPending => candidates
+                .iter()
+                .min_by_key(|instance| instance.conn.pending_requests())
+                .unwrap(),
+            Strategy::Random => candidates[random_u64() as usize % candidates.len()],
+        };
+        Ok(instance.conn.clone())
+    }
+
+    /// All connections of the pool, healthy or not.
+    pub fn connections(&self) -> impl Iterator<Item = &Arc<Connection>> {
+        self.instances.iter().map(|instance| &instance.conn)
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        for monitor in &self.monitors {
+            monitor.abort();
+        }
+    }
+}
+
+/// Configures and opens a [`Pool`].
+#[derive(Debug, Clone)]
+pub struct PoolBuilder {
+    instances: Vec<ConnectionBuilder>,
+    connections_per_instance: usize,
+    strategy: Strategy,
+    health_check_interval: Duration,
+    health_check_timeout: Duration,
+}
+
+impl Default for PoolBuilder {
+    fn default() -> Self {
+        Self {
+            instances: Vec::new(),
+            connections_per_instance: 1,
+            strategy: Strategy::default(),
+            health_check_interval: Duration::from_secs(1),
+            health_check_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl PoolBuilder {
+    /// Adds an instance connected to with the builder's settings.
+    pub fn instance(mut self, builder: ConnectionBuilder) -> Self {
+        self.instances.push(builder);
+        self
+    }
+
+    pub fn connections_per_instance(mut self, connections: usize) -> Self {
+        self.connections_per_instance = connections.max(1);
+        self
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// How often every connection is pinged, one second by default.
+    pub fn health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = interval;
+        self
+    }
+
+    /// Time a connection has to answer a ping or report its status, one second by default.
+    pub fn health_check_timeout(mut self, timeout: Duration) -> Self {
+        self.health_check_timeout = timeout;
+        self
+    }
+
+    /// Opens all connections and waits for the status of every instance.
+    /// Fails if any connection cannot be opened, use
+    /// [`ConnectionBuilder::reconnect`] to survive instances going down later.
+    pub async fn connect(self) -> io::Result<Pool> {
+        let connects = self.instances.iter().flat_map(|builder| {
+            (0..self.connections_per_instance).map(|_| builder.clone().connect())
+        });
+        let conns = futures::future::try_join_all(connects).await?;
+
+        let instances = futures::future::join_all(conns.into_iter().map(|conn| async {
+            let instance = Arc::new(Instance {
+                conn,
+                healthy: AtomicBool::new(true),
+                role: AtomicU8::new(ROLE_UNKNOWN),
+            });
+            let status = instance.watch_status(self.health_check_timeout).await;
+            (instance, status)
+        }))
+        .await;
+
+        let mut monitors = Vec::with_capacity(instances.len());
+        let mut pool_instances = Vec::with_capacity(instances.len());
+        for (instance, status) in instances {
+            let monitor = tokio::task::Builder::new()
+                .name(&instance.conn.task_name("iproto pool monitor"))
+                .spawn(instance.clone().monitor(
+                    status,
+                    self.health_check_interval,
+                    self.health_check_timeout,
+                ));
+            match monitor {
+                Ok(monitor) => monitors.push(monitor),
+                Err(err) => {
+                    monitors.iter().for_each(JoinHandle::abort);
+                    return Err(err);
+                }
+            }
+            pool_instances.push(instance);
+        }
+
+        Ok(Pool {
+            instances: pool_instances,
+            strategy: self.strategy,
+            next: AtomicUsize::new(0),
+            monitors,
+        })
+    }
+}
+
+const ROLE_UNKNOWN: u8 = 0;
+const ROLE_RW: u8 = 1;
+const ROLE_RO: u8 = 2;
+
+struct Instance {
+    conn: Arc<Connection>,
+    /// answered the last ping in time
+    healthy: AtomicBool,
+    role: AtomicU8,
+}
+
+impl Instance {
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed) && self.conn.is_connected()
+    }
+
+    fn role(&self) -> u8 {
+        self.role.load(Ordering::Relaxed)
+    }
+
+    /// Subscribes to `box.status` and waits for its first value. Servers without
+    /// watchers are asked for `box.info.ro` instead on every health check.
+    async fn watch_status(&self, timeout: Duration) -> Option<WatchStream> {
+        if !self.conn.supports_feature(Feature::Watchers) {
+            self.check_ro(timeout).await;
+            return None;
+        }
+
+        let mut status = self.conn.watch("box.status").await.ok()?;
+        if let Ok(Some(value)) = tokio::time::timeout(timeout, status.next()).await {
+            self.set_status(&value);
+        }
+        Some(status)
+    }
+
+    async fn monitor(
+        self: Arc<Self>,
+        mut status: Option<WatchStream>,
+        interval: Duration,
+        timeout: Duration,
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // the first tick completes immediately, the instance was just checked
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                Some(value) = next_status(&mut status) => self.set_status(&value),
+                _ = ticker.tick() => self.check(status.is_none(), timeout).await,
+            }
+        }
+    }
+
+    async fn check(&self, poll_ro: bool, timeout: Duration) {
+        let healthy = matches!(
+            tokio::time::timeout(timeout, self.conn.ping()).await,
+            Ok(Ok(_))
+        );
+        self.healthy.store(healthy, Ordering::Relaxed);
+
+        if healthy && poll_ro {
+            self.check_ro(timeout).await;
+        }
+    }
+
+    async fn check_ro(&self, timeout: Duration) {
+        let ro = self.conn.eval::<_, (bool,)>("return box.info.ro", &[(); 0]);
+        if let Ok(Ok((ro,))) = tokio::time::timeout(timeout, ro).await {
+            self.set_role(ro);
+        }
+    }
+
+    /// Applies a `box.status` value: `{is_ro = ..., is_ro_cfg = ..., status = ...}`.
+    fn set_status(&self, value: &rmpv::Value) {
+        let is_ro = value.as_map().and_then(|entries| {
+            entries
+                .iter()
+                .find(|(key, _)| key.as_str() == Some("is_ro"))
+                .and_then(|(_, value)| value.as_bool())
+        });
+        if let Some(is_ro) = is_ro {
+            self.set_role(is_ro);
+        }
+    }
+
+    fn set_role(&self, ro: bool) {
+        let role = if ro { ROLE_RO } else { ROLE_RW };
+        self.role.store(role, Ordering::Relaxed);
+    }
+}
+
+async fn next_status(status: &mut Option<WatchStream>) -> Option<rmpv::Value> {
+    status.as_mut()?.next().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use rmpv::Value;
+    use tokio::task::JoinHandle;
+
+    use super::{Mode, Pool, Strategy};
+    use crate::client::{Connection, Error};
+    use crate::iproto::consts;
+    use crate::testing::{MockConn, MockServer};
+
+    /// Serves every connection to the instance: reports `box.status`, answers pings
+    /// while `alive` is set and calls with the instance name, except for `hang`.
+    async fn instance(
+        name: &'static str,
+        is_ro: bool,
+        alive: Arc<AtomicBool>,
+    ) -> (String, JoinHandle<()>) {
+        let server = MockServer::bind().await;
+        let addr = server.addr().to_string();
+        let task = tokio::spawn(async move {
+            loop {
+                let server_conn = server.accept().await;
+                tokio::spawn(serve(server_conn, name, is_ro, alive.clone()));
+            }
+        });
+        (addr, task)
+    }
+
+    async fn serve(mut server_conn: MockConn, name: &str, is_ro: bool, alive: Arc<AtomicBool>) {
+        let mut is_watched = false;
+        loop {
+            let req = server_conn.next_request().await;
+            match req.request_type {
+                // later requests acknowledge events
+                consts::IPROTO_WATCH if !is_watched => {
+                    is_watched = true;
+                    let status = Value::Map(vec![
+                        ("is_ro".into(), is_ro.into()),
+                        ("is_ro_cfg".into(), is_ro.into()),
+                        ("status".into(), "running".into()),
+                    ]);
+                    server_conn.send_event("box.status", status).await;
+                }
+                consts::IPROTO_PING if alive.load(Ordering::Relaxed) => {
+                    server_conn.reply(req.sync, Value::Map(vec![])).await;
+                }
+                consts::IPROTO_CALL => {
+                    let function = req.body_value(consts::IPROTO_FUNCTION_NAME);
+                    if function.and_then(Value::as_str) != Some("hang") {
+                        let data = Value::Array(vec![name.into()]);
+                        server_conn.reply_data(req.sync, data).await;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn whoami(pool: &Pool, mode: Mode) -> String {
+        let (name,): (String,) = pool
+            .get(mode)
+            .unwrap()
+            .call("whoami", &[(); 0])
+            .await
+            .unwrap();
+        name
+    }
+
+    #[tokio::test]
+    async fn pool_mode_test() {
+        let alive = Arc::new(AtomicBool::new(true));
+        let (master_addr, master) = instance("master", false, alive.clone()).await;
+        let (replica_addr, replica) = instance("replica", true, alive.clone()).await;
+
+        let pool = Pool::connect([master_addr, replica_addr]).await.unwrap();
+        assert_eq!(pool.connections().count(), 2);
+
+        assert_eq!(whoami(&pool, Mode::ReadWrite).await, "master");
+        assert_eq!(whoami(&pool, Mode::ReadOnly).await, "replica");
+        assert_eq!(whoami(&pool, Mode::PreferReplica).await, "replica");
+
+        master.abort();
+        replica.abort();
+    }
+
+    #[tokio::test]
+    async fn pool_strategy_test() {
+        let alive = Arc::new(AtomicBool::new(true));
+        let (first_addr, first) = instance("first", true, alive.clone()).await;
+        let (second_addr, second) = instance("second", true, alive.clone()).await;
+        let builder = |strategy| {
+            Pool::builder()
+                .instance(Connection::builder(first_addr.clone()))
+                .instance(Connection::builder(second_addr.clone()))
+                .strategy(strategy)
+        };
+
+        let pool = builder(Strategy::RoundRobin).connect().await.unwrap();
+        let names = [
+            whoami(&pool, Mode::ReadOnly).await,
+            whoami(&pool, Mode::ReadOnly).await,
+            whoami(&pool, Mode::ReadOnly).await,
+        ];
+        assert_ne!(names[0], names[1]);
+        assert_eq!(names[0], names[2]);
+
+        let pool = builder(Strategy::LeastPending)
+            .connections_per_instance(2)
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(pool.connections().count(), 4);
+        let busy = pool.get(Mode::ReadOnly).unwrap();
+        let hang = tokio::spawn(async move {
+            let _: Result<Value, Error> = busy.call("hang", &[(); 0]).await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let pending: Vec<usize> = pool
+            .get(Mode::ReadOnly)
+            .into_iter()
+            .chain(pool.get(Mode::ReadOnly))
+            .chain(pool.get(Mode::ReadOnly))
+            .map(|conn| conn.pending_requests())
+            .collect();
+        assert_eq!(pending, [0, 0, 0]);
+        hang.abort();
+
+        let pool = builder(Strategy::Random).connect().await.unwrap();
+        let name = whoami(&pool, Mode::PreferReplica).await;
+        assert!(name == "first" || name == "second");
+
+        first.abort();
+        second.abort();
+    }
+
+    #[tokio::test]
+    async fn pool_health_check_test() {
+        let alive = Arc::new(AtomicBool::new(true));
+        let (replica_addr, replica) = instance("replica", true, alive.clone()).await;
+
+        let pool = Pool::builder()
+            .instance(Connection::builder(replica_addr))
+            .health_check_interval(Duration::from_millis(20))
+            .health_check_timeout(Duration::from_millis(20))
+            .connect()
+            .await
+            .unwrap();
+        assert!(matches!(
+            pool.get(Mode::ReadWrite),
+            Err(Error::NoAvailableInstance(Mode::ReadWrite))
+        ));
+        assert_eq!(whoami(&pool, Mode::PreferReplica).await, "replica");
+
+        // the replica stops answering pings
+        alive.store(false, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            pool.get(Mode::ReadOnly),
+            Err(Error::NoAvailableInstance(Mode::ReadOnly))
+        ));
+
+        alive.store(true, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(whoami(&pool, Mode::ReadOnly).await, "replica");
+
+        replica.abort();
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use crate::utils::random_u64;
+
+/// Exponential backoff used to re-dial a lost connection.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// delay before the first attempt
+    pub initial_backoff: Duration,
+    /// upper bound for the delay between attempts
+    pub max_backoff: Duration,
+    /// factor the delay grows by after every failed attempt
+    pub multiplier: f64,
+    /// fraction of the delay randomized to spread reconnects of many clients, in `0.0..=1.0`
+    pub jitter: f64,
+    /// give up after this many failed attempts, retry forever if `None`
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        let base = base.min(self.max_backoff.as_secs_f64());
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 - jitter + 2.0 * jitter * random_fraction();
+        // negative, NaN or overflowing delays come from odd policies, not worth a panic
+        Duration::try_from_secs_f64(base * factor).unwrap_or(self.max_backoff)
+    }
+
+    pub(crate) fn is_exhausted(&self, failed_attempts: u32) -> bool {
+        self.max_attempts
+            .is_some_and(|max_attempts| failed_attempts >= max_attempts)
+    }
+}
+
+/// Random number in `0.0..1.0` good enough for jitter.
+fn random_fraction() -> f64 {
+    (random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReconnectPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn backoff_test() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_attempts: Some(3),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_secs(1));
+
+        assert!(!policy.is_exhausted(2));
+        assert!(policy.is_exhausted(3));
+
+        let policy = ReconnectPolicy {
+            jitter: 0.5,
+            ..policy
+        };
+        for _ in 0..100 {
+            let backoff = policy.backoff(0);
+            assert!(backoff >= Duration::from_millis(50));
+            assert!(backoff <= Duration::from_millis(150));
+        }
+
+        let odd_policies = [
+            ReconnectPolicy {
+                multiplier: -2.0,
+                ..policy.clone()
+            },
+            ReconnectPolicy {
+                multiplier: f64::NAN,
+                ..policy.clone()
+            },
+            ReconnectPolicy {
+                jitter: f64::NAN,
+                ..policy.clone()
+            },
+        ];
+        for policy in odd_policies {
+            // max_backoff with up to 50% jitter
+            assert!(policy.backoff(1) <= Duration::from_millis(1500));
+        }
+
+        let uncapped = ReconnectPolicy {
+            max_backoff: Duration::MAX,
+            multiplier: 1e300,
+            ..policy
+        };
+        for attempt in 0..10 {
+            uncapped.backoff(attempt);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Spaces and indexes of the server, loaded from the `_vspace` and `_vindex` system views.
+
+use std::collections::HashMap;
+
+use rmpv::Value;
+use serde::de::IgnoredAny;
+
+/// `_vspace` tuple: id, owner, name, engine, field count, flags and format.
+pub(crate) type SpaceTuple = (
+    u32,
+    u32,
+    String,
+    String,
+    u32,
+    IgnoredAny,
+    Vec<HashMap<String, Value>>,
+);
+
+/// `_vindex` tuple: space id, index id, name, type, options and parts.
+pub(crate) type IndexTuple = (u32, u32, String, String, HashMap<String, Value>, Vec<Value>);
+
+/// Snapshot of the server schema, reloaded by [`Connection::schema`](crate::client::Connection::schema)
+/// once the server reports a different schema version.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    version: u64,
+    spaces: HashMap<u32, Space>,
+    space_ids: HashMap<String, u32>,
+}
+
+impl Schema {
+    pub(crate) fn new(version: u64, spaces: Vec<SpaceTuple>, indexes: Vec<IndexTuple>) -> Self {
+        let mut spaces: HashMap<u32, Space> = spaces
+            .into_iter()
+            .map(|(id, _, name, engine, field_count, _, format)| {
+                let space = Space {
+                    id,
+                    name,
+                    engine,
+                    field_count,
+                    format: format.iter().map(FieldFormat::from_map).collect(),
+                    indexes: Vec::new(),
+                };
+                (id, space)
+            })
+            .collect();
+
+        for (space_id, id, name, index_type, opts, parts) in indexes {
+            let Some(space) = spaces.get_mut(&space_id) else {
+                continue;
+            };
+            space.indexes.push(Index {
+                space_id,
+                id,
+                name,
+                index_type,
+                unique: opts.get("unique").and_then(Value::as_bool).unwrap_or(false),
+                parts: parts.iter().filter_map(IndexPart::from_value).collect(),
+            });
+        }
+        for space in spaces.values_mut() {
+            space.indexes.sort_by_key(|index| index.id);
+        }
+
+        let space_ids = spaces
+            .values()
+            .map(|space| (space.name.clone(), space.id))
+            .collect();
+        Self {
+            version,
+            spaces,
+            space_ids,
+        }
+    }
+
+    /// Schema version the snapshot was loaded at.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn space(&self, name: &str) -> Option<&Space> {
+        self.spaces.get(self.space_ids.get(name)?)
+    }
+
+    pub fn space_by_id(&self, id: u32) -> Option<&Space> {
+        self.spaces.get(&id)
+    }
+
+    pub fn spaces(&self) -> impl Iterator<Item = &Space> {
+        self.spaces.values()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Space {
+    pub id: u32,
+    pub name: String,
+    pub engine: String,
+    /// required number of fields, 0 if not enforced
+    pub field_count: u32,
+    pub format: Vec<FieldFormat>,
+    /// sorted by id, the primary index goes first
+    pub indexes: Vec<Index>,
+}
+
+impl Space {
+    pub fn index(&self, name: &str) -> Option<&Index> {
+        self.indexes.iter().find(|index| index.name == name)
+    }
+
+    pub fn index_by_id(&self, id: u32) -> Option<&Index> {
+        self.indexes.iter().find(|index| index.id == id)
+    }
+
+    /// Zero-based position of the field in a tuple.
+    pub fn field_no(&self, name: &str) -> Option<usize> {
+        self.format.iter().position(|field| field.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldFormat {
+    pub name: String,
+    pub field_type: String,
+    pub is_nullable: bool,
+}
+
+impl FieldFormat {
+    fn from_map(map: &HashMap<String, Value>) -> Self {
+        let str_value = |key: &str| {
+            map.get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned()
+        };
+        Self {
+            name: str_value("name"),
+            field_type: str_value("type"),
+            is_nullable: map
+                .get("is_nullable")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub space_id: u32,
+    pub id: u32,
+    pub name: String,
+    /// `tree`, `hash`, `bitset` or `rtree`
+    pub index_type: String,
+    pub unique: bool,
+    pub parts: Vec<IndexPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexPart {
+    /// zero-based field number
+    pub field: u32,
+    pub field_type: String,
+    pub is_nullable: bool,
+    /// JSON path inside the field for multikey and nested indexes
+    pub path: Option<String>,
+}
+
+impl IndexPart {
+    /// Parses both `{field = 1, type = 'unsigned'}` and the pre-1.10 `[1, 'unsigned']` forms.
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(entries) => {
+                let get = |key: &str| {
+                    entries
+                        .iter()
+                        .find(|(k, _)| k.as_str() == Some(key))
+                        .map(|(_, v)| v)
+                };
+                Some(Self {
+                    field: get("field")?.as_u64()? as u32,
+                    field_type: get("type")?.as_str()?.to_owned(),
+                    is_nullable: get("is_nullable").and_then(Value::as_bool).unwrap_or(false),
+                    path: get("path").and_then(Value::as_str).map(str::to_owned),
+                })
+            }
+            Value::Array(items) => Some(Self {
+                field: items.first()?.as_u64()? as u32,
+                field_type: items.get(1)?.as_str()?.to_owned(),
+                is_nullable: false,
+                path: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, Value)]) -> HashMap<String, Value> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn schema_test() {
+        let format = vec![
+            map(&[("name", "id".into()), ("type", "unsigned".into())]),
+            map(&[
+                ("name", "name".into()),
+                ("type", "string".into()),
+                ("is_nullable", true.into()),
+            ]),
+        ];
+        let spaces = vec![(
+            512,
+            1,
+            "test".to_owned(),
+            "memtx".to_owned(),
+            0,
+            IgnoredAny,
+            format,
+        )];
+        let indexes = vec![
+            (
+                512,
+                1,
+                "name".to_owned(),
+                "tree".to_owned(),
+                map(&[("unique", false.into())]),
+                vec![Value::Array(vec![1.into(), "string".into()])],
+            ),
+            (
+                512,
+                0,
+                "primary".to_owned(),
+                "tree".to_owned(),
+                map(&[("unique", true.into())]),
+                vec![Value::Map(vec![
+                    ("field".into(), 0.into()),
+                    ("type".into(), "unsigned".into()),
+                ])],
+            ),
+            // index of a space hidden from the user
+            (
+                600,
+                0,
+                "primary".to_owned(),
+                "tree".to_owned(),
+                HashMap::new(),
+                vec![],
+            ),
+        ];
+
+        let schema = Schema::new(80, spaces, indexes);
+        assert_eq!(schema.version(), 80);
+        assert_eq!(schema.spaces().count(), 1);
+
+        let space = schema.space("test").unwrap();
+        assert_eq!(space.id, 512);
+        assert_eq!(space.engine, "memtx");
+        assert_eq!(space.field_no("name"), Some(1));
+        assert!(space.format[1].is_nullable);
+        assert_eq!(schema.space_by_id(512).unwrap().name, "test");
+        assert!(schema.space("missing").is_none());
+
+        let ids: Vec<u32> = space.indexes.iter().map(|index| index.id).collect();
+        assert_eq!(ids, [0, 1]);
+        let primary = space.index("primary").unwrap();
+        assert!(primary.unique);
+        assert_eq!(primary.parts[0].field_type, "unsigned");
+
+        let secondary = space.index_by_id(1).unwrap();
+        assert!(!secondary.unique);
+        assert_eq!(
+            secondary.parts,
+            [IndexPart {
+                field: 1,
+                field_type: "string".to_owned(),
+                is_nullable: false,
+                path: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Minimal iproto server for tests that cannot rely on a live Tarantool instance.
+
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64Engine;
+use rmpv::Value;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+
+use crate::iproto::consts;
+use crate::iproto::greeting::GREETING_SIZE;
+
+pub enum MockServer {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+    #[cfg(feature = "tls")]
+    Tls(TcpListener, tokio_rustls::TlsAcceptor),
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+impl MockServer {
+    pub async fn bind() -> Self {
+        Self::Tcp(TcpListener::bind("127.0.0.1:0").await.unwrap())
+    }
+
+    /// Listens on a unix socket at `path`, replacing a stale socket file.
+    pub fn bind_unix(path: &Path) -> Self {
+        let _ = std::fs::remove_file(path);
+        Self::Unix(UnixListener::bind(path).unwrap())
+    }
+
+    /// Terminates TLS in front of the mock server.
+    #[cfg(feature = "tls")]
+    pub async fn bind_tls(acceptor: tokio_rustls::TlsAcceptor) -> Self {
+        Self::Tls(TcpListener::bind("127.0.0.1:0").await.unwrap(), acceptor)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().unwrap(),
+            #[cfg(feature = "tls")]
+            Self::Tls(listener, _) => listener.local_addr().unwrap(),
+            Self::Unix(_) => panic!("unix socket has no address"),
+        }
+    }
+
+    /// Accepts a connection and sends the greeting.
+    pub async fn accept(&self) -> MockConn {
+        self.accept_with_greeting(&greeting(&[7; 32])).await
+    }
+
+    pub async fn accept_with_greeting(&self, greeting: &[u8; GREETING_SIZE]) -> MockConn {
+        let mut stream: Box<dyn Io> = match self {
+            Self::Tcp(listener) => Box::new(listener.accept().await.unwrap().0),
+            Self::Unix(listener) => Box::new(listener.accept().await.unwrap().0),
+            #[cfg(feature = "tls")]
+            Self::Tls(listener, acceptor) => {
+                let stream = listener.accept().await.unwrap().0;
+                Box::new(acceptor.accept(stream).await.unwrap())
+            }
+        };
+        stream.write_all(greeting).await.unwrap();
+        MockConn {
+            stream,
+            schema_version: None,
+            features: (0..=9).collect(),
+            auth_type: None,
+        }
+    }
+}
+
+/// Greeting of a 2.11 server with the base64 encoded salt.
+pub fn greeting(salt: &[u8]) -> [u8; GREETING_SIZE] {
+    let version_line = "Tarantool 2.11.0 (Binary) 7170b4af-c72f-4f07-8729-08fc678543a5";
+    let salt = Base64Engine.encode(salt);
+
+    let mut raw = [b' '; GREETING_SIZE];
+    raw[..version_line.len()].copy_from_slice(version_line.as_bytes());
+    raw[63] = b'\n';
+    raw[64..64 + salt.len()].copy_from_slice(salt.as_bytes());
+    raw[127] = b'\n';
+    raw
+}
+
+#[derive(Debug)]
+pub struct MockRequest {
+    pub request_type: u8,
+    pub sync: u64,
+    pub body: Value,
+}
+
+impl MockRequest {
+    pub fn body_value(&self, key: u8) -> Option<&Value> {
+        self.body
+            .as_map()?
+            .iter()
+            .find(|(k, _)| k.as_u64() == Some(key as u64))
+            .map(|(_, v)| v)
+    }
+}
+
+pub struct MockConn {
+    stream: Box<dyn Io>,
+    /// reported in the header of every response if set
+    pub schema_version: Option<u64>,
+    /// announced in the IPROTO_ID response
+    pub features: Vec<u8>,
+    /// announced in the IPROTO_ID response if set
+    pub auth_type: Option<String>,
+}
+
+impl MockConn {
+    /// Reads the next request, answering IPROTO_ID on the way.
+    pub async fn next_request(&mut self) -> MockRequest {
+        loop {
+            let req = self.read_request().await;
+            if req.request_type != consts::IPROTO_ID {
+                return req;
+            }
+
+            let features = self.features.iter().copied().map(Value::from).collect();
+            let mut body = vec![
+                (consts::IPROTO_VERSION.into(), 6.into()),
+                (consts::IPROTO_FEATURES.into(), Value::Array(features)),
+            ];
+            if let Some(auth_type) = &self.auth_type {
+                body.push((consts::IPROTO_AUTH_TYPE.into(), auth_type.as_str().into()));
+            }
+            let body = Value::Map(body);
+            self.reply(req.sync, body).await;
+        }
+    }
+
+    pub async fn read_request(&mut self) -> MockRequest {
+        let mut len_raw = [0; 5];
+        self.stream.read_exact(&mut len_raw).await.unwrap();
+        let len = u32::from_be_bytes(len_raw[1..].try_into().unwrap()) as usize;
+
+        let mut payload = vec![0; len];
+        self.stream.read_exact(&mut payload).await.unwrap();
+        let mut cursor = Cursor::new(payload);
+
+        let header = rmpv::decode::read_value(&mut cursor).unwrap();
+        let body = if (cursor.position() as usize) < cursor.get_ref().len() {
+            rmpv::decode::read_value(&mut cursor).unwrap()
+        } else {
+            Value::Nil
+        };
+
+        let header_value = |key: u8| {
+            header
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(k, _)| k.as_u64() == Some(key as u64))
+                .and_then(|(_, v)| v.as_u64())
+                .unwrap()
+        };
+        MockRequest {
+            request_type: header_value(consts::IPROTO_REQUEST_TYPE) as u8,
+            sync: header_value(consts::IPROTO_SYNC),
+            body,
+        }
+    }
+
+    pub async fn reply(&mut self, sync: u64, body: Value) {
+        self.send(consts::IPROTO_OK as u32, sync, body).await;
+    }
+
+    /// Replies with `IPROTO_DATA` holding `data`.
+    pub async fn reply_data(&mut self, sync: u64, data: Value) {
+        let body = Value::Map(vec![(consts::IPROTO_DATA.into(), data)]);
+        self.reply(sync, body).await;
+    }
+
+    pub async fn send(&mut self, code: u32, sync: u64, body: Value) {
+        let mut payload = match self.schema_version {
+            Some(schema_version) => header_with_schema(code, sync, schema_version),
+            None => header(code, sync),
+        };
+        rmpv::encode::write_value(&mut payload, &body).unwrap();
+        self.write_raw(&frame(&payload)).await;
+    }
+
+    /// Sends an IPROTO_EVENT with the new value of the key.
+    pub async fn send_event(&mut self, key: &str, data: Value) {
+        let body = Value::Map(vec![
+            (consts::IPROTO_EVENT_KEY.into(), key.into()),
+            (consts::IPROTO_EVENT_DATA.into(), data),
+        ]);
+        self.send(consts::IPROTO_EVENT as u32, 0, body).await;
+    }
+
+    pub async fn write_raw(&mut self, bytes: &[u8]) {
+        self.stream.write_all(bytes).await.unwrap();
+    }
+}
+
+/// Encodes a response header.
+pub fn header(code: u32, sync: u64) -> Vec<u8> {
+    let mut header = Vec::new();
+    rmp::encode::write_map_len(&mut header, 2).unwrap();
+    rmp::encode::write_pfix(&mut header, consts::RESPONSE_CODE_INDICATOR).unwrap();
+    rmp::encode::write_uint(&mut header, code as u64).unwrap();
+    rmp::encode::write_pfix(&mut header, consts::IPROTO_SYNC).unwrap();
+    rmp::encode::write_u64(&mut header, sync).unwrap();
+    header
+}
+
+/// Encodes a response header carrying `IPROTO_SCHEMA_VERSION`.
+pub fn header_with_schema(code: u32, sync: u64, schema_version: u64) -> Vec<u8> {
+    let mut header = Vec::new();
+    rmp::encode::write_map_len(&mut header, 3).unwrap();
+    rmp::encode::write_pfix(&mut header, consts::RESPONSE_CODE_INDICATOR).unwrap();
+    rmp::encode::write_uint(&mut header, code as u64).unwrap();
+    rmp::encode::write_pfix(&mut header, consts::IPROTO_SYNC).unwrap();
+    rmp::encode::write_u64(&mut header, sync).unwrap();
+    rmp::encode::write_pfix(&mut header, consts::IPROTO_SCHEMA_VERSION).unwrap();
+    rmp::encode::write_uint(&mut header, schema_version).unwrap();
+    header
+}
+
+/// Prepends the payload with its length.
+pub fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0xCE];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
@@ -0,0 +1,183 @@
+//! TLS transport for servers listening with `transport = 'ssl'`, enabled by the `tls` feature.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+
+use crate::transport::Address;
+
+#[derive(Clone)]
+enum Pem {
+    File(PathBuf),
+    Data(Vec<u8>),
+}
+
+impl Pem {
+    fn read(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Pem::File(path) => std::fs::read(path),
+            Pem::Data(data) => Ok(data.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for Pem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pem::File(path) => f.debug_tuple("File").field(path).finish(),
+            // may hold a private key
+            Pem::Data(_) => f.write_str("Data(..)"),
+        }
+    }
+}
+
+/// TLS settings for [`ConnectionBuilder::tls`](crate::client::ConnectionBuilder::tls).
+/// The server certificate is verified against the configured CA certificates only.
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    ca: Vec<Pem>,
+    client_cert: Option<(Pem, Pem)>,
+    server_name: Option<String>,
+    ciphers: Option<Vec<String>>,
+}
+
+impl TlsOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts the CA certificates from a PEM file.
+    pub fn ca_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca.push(Pem::File(path.into()));
+        self
+    }
+
+    /// Trusts PEM encoded CA certificates.
+    pub fn ca_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca.push(Pem::Data(pem.into()));
+        self
+    }
+
+    /// Certificate chain and private key PEM files presented to servers requiring client authentication.
+    pub fn client_cert_file(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.client_cert = Some((Pem::File(cert.into()), Pem::File(key.into())));
+        self
+    }
+
+    /// Like [`TlsOptions::client_cert_file`], but with PEM encoded data.
+    pub fn client_cert_pem(mut self, cert: impl Into<Vec<u8>>, key: impl Into<Vec<u8>>) -> Self {
+        self.client_cert = Some((Pem::Data(cert.into()), Pem::Data(key.into())));
+        self
+    }
+
+    /// Name sent with SNI and expected in the server certificate, the host of the URI by default.
+    pub fn server_name(mut self, name: &str) -> Self {
+        self.server_name = Some(name.to_owned());
+        self
+    }
+
+    /// Restricts cipher suites to the listed ones, named like `TLS13_AES_256_GCM_SHA384`.
+    pub fn ciphers(mut self, ciphers: &[&str]) -> Self {
+        self.ciphers = Some(ciphers.iter().map(|&cipher| cipher.to_owned()).collect());
+        self
+    }
+}
+
+/// Client side of the TLS handshake built from [`TlsOptions`].
+pub(crate) struct Tls {
+    connector: TlsConnector,
+    server_name: ServerName<'static>,
+}
+
+impl Tls {
+    pub fn new(options: &TlsOptions, address: &Address) -> io::Result<Self> {
+        let host = match address {
+            Address::Tcp(addr) => addr
+                .rsplit_once(':')
+                .map_or(addr.as_str(), |(host, _)| host),
+            Address::Unix(_) => return Err(invalid("TLS is only supported over TCP")),
+        };
+        let server_name = match &options.server_name {
+            Some(name) => name.clone(),
+            None => host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_owned(),
+        };
+        let server_name = ServerName::try_from(server_name).map_err(invalid)?;
+
+        let mut roots = RootCertStore::empty();
+        for pem in &options.ca {
+            for cert in read_certs(pem)? {
+                roots.add(cert).map_err(invalid)?;
+            }
+        }
+        if roots.is_empty() {
+            return Err(invalid("no CA certificates configured"));
+        }
+
+        let mut provider = rustls::crypto::ring::default_provider();
+        if let Some(ciphers) = &options.ciphers {
+            let name = |suite: &rustls::SupportedCipherSuite| format!("{:?}", suite.suite());
+            if let Some(unknown) = ciphers
+                .iter()
+                .find(|&cipher| !provider.cipher_suites.iter().any(|s| name(s) == *cipher))
+            {
+                return Err(invalid(format!("unknown cipher suite {unknown}")));
+            }
+            provider
+                .cipher_suites
+                .retain(|suite| ciphers.contains(&name(suite)));
+        }
+
+        let builder = ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_safe_default_protocol_versions()
+            .map_err(invalid)?
+            .with_root_certificates(roots);
+        let config = match &options.client_cert {
+            Some((cert, key)) => builder
+                .with_client_auth_cert(read_certs(cert)?, read_key(key)?)
+                .map_err(invalid)?,
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(Self {
+            connector: TlsConnector::from(Arc::new(config)),
+            server_name,
+        })
+    }
+
+    pub async fn connect(&self, stream: TcpStream) -> io::Result<TlsStream<TcpStream>> {
+        self.connector
+            .connect(self.server_name.clone(), stream)
+            .await
+    }
+}
+
+impl fmt::Debug for Tls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tls")
+            .field("server_name", &self.server_name)
+            .finish_non_exhaustive()
+    }
+}
+
+fn read_certs(pem: &Pem) -> io::Result<Vec<CertificateDer<'static>>> {
+    rustls_pemfile::certs(&mut pem.read()?.as_slice()).collect()
+}
+
+fn read_key(pem: &Pem) -> io::Result<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut pem.read()?.as_slice())?
+        .ok_or_else(|| invalid("no private key found"))
+}
+
+fn invalid(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
@@ -0,0 +1,258 @@
+//! Streams the connection runs over and the addresses they are opened from.
+
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpStream, UnixStream, tcp, unix};
+
+use crate::builder::Credentials;
+
+/// Parsed Tarantool URI: `[login[:password]@]host:port`, `[login[:password]@]unix/:/path`,
+/// a bare port for localhost or an absolute path of a unix socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Uri {
+    pub address: Address,
+    pub credentials: Option<Credentials>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Address {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl Uri {
+    pub fn parse(uri: &str) -> io::Result<Self> {
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid uri {uri:?}: {reason}"),
+            )
+        };
+
+        // unix socket paths may contain '@' themselves
+        let (credentials, location) = match uri.find("unix/:") {
+            Some(0) => (None, uri),
+            Some(pos) => match uri[..pos].strip_suffix('@') {
+                Some(credentials) => (Some(credentials), &uri[pos..]),
+                None => return Err(invalid("unexpected characters before unix/:")),
+            },
+            None => match uri.rsplit_once('@') {
+                Some((credentials, location)) => (Some(credentials), location),
+                None => (None, uri),
+            },
+        };
+
+        let credentials = match credentials {
+            Some(credentials) => {
+                let (username, password) = match credentials.split_once(':') {
+                    Some((username, password)) => (username, Some(password.to_owned())),
+                    None => (credentials, None),
+                };
+                if username.is_empty() {
+                    return Err(invalid("empty login"));
+                }
+                Some(Credentials {
+                    username: username.to_owned(),
+                    password,
+                    method: None,
+                })
+            }
+            None => None,
+        };
+
+        let address = if let Some(path) = location.strip_prefix("unix/:") {
+            if path.is_empty() {
+                return Err(invalid("empty socket path"));
+            }
+            Address::Unix(path.into())
+        } else if location.starts_with('/') || location.starts_with("./") {
+            Address::Unix(location.into())
+        } else if !location.is_empty() && location.bytes().all(|b| b.is_ascii_digit()) {
+            Address::Tcp(format!("localhost:{location}"))
+        } else if location.contains(':') {
+            Address::Tcp(location.to_owned())
+        } else {
+            return Err(invalid("missing port"));
+        };
+
+        Ok(Self {
+            address,
+            credentials,
+        })
+    }
+}
+
+impl Address {
+    /// Resolves host names once, reconnects reuse the result.
+    pub async fn resolve(&self) -> io::Result<Endpoint> {
+        match self {
+            Address::Tcp(addr) => Ok(Endpoint::Tcp(
+                tokio::net::lookup_host(addr).await?.collect(),
+            )),
+            Address::Unix(path) => Ok(Endpoint::Unix(path.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Endpoint {
+    Tcp(Vec<SocketAddr>),
+    Unix(PathBuf),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $stream:ident => $expr:expr) => {
+        match $self.get_mut() {
+            Self::Tcp($stream) => $expr,
+            Self::Unix($stream) => $expr,
+            #[cfg(feature = "tls")]
+            Self::Tls($stream) => $expr,
+        }
+    };
+}
+
+pub(crate) enum Socket {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl Socket {
+    pub fn into_split(self) -> (ReadHalf, WriteHalf) {
+        match self {
+            Socket::Tcp(stream) => {
+                let (read, write) = stream.into_split();
+                (ReadHalf::Tcp(read), WriteHalf::Tcp(write))
+            }
+            Socket::Unix(stream) => {
+                let (read, write) = stream.into_split();
+                (ReadHalf::Unix(read), WriteHalf::Unix(write))
+            }
+            #[cfg(feature = "tls")]
+            Socket::Tls(stream) => {
+                let (read, write) = tokio::io::split(*stream);
+                (ReadHalf::Tls(read), WriteHalf::Tls(write))
+            }
+        }
+    }
+}
+
+impl AsyncRead for Socket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        dispatch!(self, stream => Pin::new(stream).poll_read(cx, buf))
+    }
+}
+
+impl AsyncWrite for Socket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        dispatch!(self, stream => Pin::new(stream).poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch!(self, stream => Pin::new(stream).poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch!(self, stream => Pin::new(stream).poll_shutdown(cx))
+    }
+}
+
+pub(crate) enum ReadHalf {
+    Tcp(tcp::OwnedReadHalf),
+    Unix(unix::OwnedReadHalf),
+    #[cfg(feature = "tls")]
+    Tls(tokio::io::ReadHalf<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for ReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        dispatch!(self, stream => Pin::new(stream).poll_read(cx, buf))
+    }
+}
+
+pub(crate) enum WriteHalf {
+    Tcp(tcp::OwnedWriteHalf),
+    Unix(unix::OwnedWriteHalf),
+    #[cfg(feature = "tls")]
+    Tls(tokio::io::WriteHalf<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl AsyncWrite for WriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        dispatch!(self, stream => Pin::new(stream).poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch!(self, stream => Pin::new(stream).poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch!(self, stream => Pin::new(stream).poll_shutdown(cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Address, Uri};
+
+    #[test]
+    fn uri_test() {
+        let uri = Uri::parse("localhost:3301").unwrap();
+        assert_eq!(uri.address, Address::Tcp("localhost:3301".into()));
+        assert!(uri.credentials.is_none());
+
+        let uri = Uri::parse("3301").unwrap();
+        assert_eq!(uri.address, Address::Tcp("localhost:3301".into()));
+
+        let uri = Uri::parse("user:p@ss@[::1]:3301").unwrap();
+        assert_eq!(uri.address, Address::Tcp("[::1]:3301".into()));
+        assert!(!format!("{uri:?}").contains("p@ss"));
+        let credentials = uri.credentials.unwrap();
+        assert_eq!(credentials.username, "user");
+        assert_eq!(credentials.password.as_deref(), Some("p@ss"));
+
+        let uri = Uri::parse("unix/:/var/run/tarantool.sock").unwrap();
+        assert_eq!(uri.address, Address::Unix("/var/run/tarantool.sock".into()));
+
+        let uri = Uri::parse("guest@unix/:./run/t@1.sock").unwrap();
+        assert_eq!(uri.address, Address::Unix("./run/t@1.sock".into()));
+        let credentials = uri.credentials.unwrap();
+        assert_eq!(credentials.username, "guest");
+        assert!(credentials.password.is_none());
+
+        let uri = Uri::parse("/tmp/tarantool.sock").unwrap();
+        assert_eq!(uri.address, Address::Unix("/tmp/tarantool.sock".into()));
+
+        for invalid in [
+            "",
+            "localhost",
+            "unix/:",
+            ":pass@host:3301",
+            "x unix/:/path",
+        ] {
+            assert!(Uri::parse(invalid).is_err(), "{invalid:?}");
+        }
+    }
+}
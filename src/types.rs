@@ -0,0 +1,509 @@
+//! Tarantool types encoded as MsgPack extensions. They can be used in call arguments,
+//! tuples and results like any other serde type.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rmp_serde::MSGPACK_EXT_STRUCT_NAME;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{self, Serialize, Serializer};
+use serde_bytes::{ByteBuf, Bytes};
+
+use crate::iproto::consts;
+use crate::iproto::response::ErrorExtra;
+
+fn serialize_ext<S: Serializer>(serializer: S, tag: i8, data: &[u8]) -> Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_struct(MSGPACK_EXT_STRUCT_NAME, &(tag, Bytes::new(data)))
+}
+
+fn deserialize_ext<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    tag: i8,
+) -> Result<Vec<u8>, D::Error> {
+    struct ExtVisitor;
+
+    impl<'de> de::Visitor<'de> for ExtVisitor {
+        type Value = (i8, ByteBuf);
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("msgpack extension")
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            Deserialize::deserialize(deserializer)
+        }
+    }
+
+    let (actual_tag, data) =
+        deserializer.deserialize_newtype_struct(MSGPACK_EXT_STRUCT_NAME, ExtVisitor)?;
+    if actual_tag != tag {
+        return Err(de::Error::custom(format!(
+            "expected extension type {tag}, got {actual_tag}"
+        )));
+    }
+    Ok(data.into_vec())
+}
+
+/// `decimal`: up to 38 significant digits, the value is `mantissa * 10^-scale`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    pub mantissa: i128,
+    pub scale: i32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: i32) -> Self {
+        Self { mantissa, scale }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        rmp::encode::write_sint(&mut data, self.scale as i64).unwrap();
+
+        // packed BCD, the last nibble is the sign
+        let mut nibbles: Vec<u8> = self
+            .mantissa
+            .unsigned_abs()
+            .to_string()
+            .bytes()
+            .map(|digit| digit - b'0')
+            .collect();
+        nibbles.push(if self.mantissa < 0 { 0x0d } else { 0x0c });
+        if nibbles.len() % 2 == 1 {
+            nibbles.insert(0, 0);
+        }
+        data.extend(nibbles.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+        data
+    }
+
+    fn decode(mut data: &[u8]) -> Option<Self> {
+        let scale: i32 = rmp::decode::read_int(&mut data).ok()?;
+        let (&last, digits) = data.split_last()?;
+
+        let mut mantissa: i128 = 0;
+        let nibbles = digits
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0x0f])
+            .chain([last >> 4]);
+        for nibble in nibbles {
+            if nibble > 9 {
+                return None;
+            }
+            mantissa = mantissa.checked_mul(10)?.checked_add(nibble as i128)?;
+        }
+
+        let mantissa = match last & 0x0f {
+            0x0b | 0x0d => -mantissa,
+            _ => mantissa,
+        };
+        Some(Self { mantissa, scale })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+
+        if self.scale <= 0 {
+            let zeros = "0".repeat(self.scale.unsigned_abs() as usize);
+            let zeros = if self.mantissa == 0 { "" } else { &zeros };
+            return write!(f, "{sign}{digits}{zeros}");
+        }
+
+        let scale = self.scale as usize;
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{int}.{frac}")
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(ParseError("decimal"));
+        }
+
+        let mut mantissa: i128 = 0;
+        for c in int.chars().chain(frac.chars()) {
+            let digit = c.to_digit(10).ok_or(ParseError("decimal"))?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit as i128))
+                .ok_or(ParseError("decimal"))?;
+        }
+
+        let mantissa = if negative { -mantissa } else { mantissa };
+        Ok(Self::new(mantissa, frac.len() as i32))
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ext(serializer, consts::MP_DECIMAL, &self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = deserialize_ext(deserializer, consts::MP_DECIMAL)?;
+        Self::decode(&data).ok_or_else(|| de::Error::custom("invalid decimal"))
+    }
+}
+
+/// `uuid`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Uuid(pub [u8; 16]);
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex: Vec<u8> = s.bytes().filter(|&c| c != b'-').collect();
+        if hex.len() != 32 || s.len() != 36 {
+            return Err(ParseError("uuid"));
+        }
+
+        let mut uuid = [0; 16];
+        for (byte, pair) in uuid.iter_mut().zip(hex.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| ParseError("uuid"))?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| ParseError("uuid"))?;
+        }
+        Ok(Self(uuid))
+    }
+}
+
+impl Serialize for Uuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ext(serializer, consts::MP_UUID, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Uuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = deserialize_ext(deserializer, consts::MP_UUID)?;
+        let uuid = data
+            .try_into()
+            .map_err(|_| de::Error::custom("invalid uuid"))?;
+        Ok(Self(uuid))
+    }
+}
+
+/// `datetime`: seconds and nanoseconds since the Unix epoch in UTC
+/// with the timezone offset in minutes and the timezone index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Datetime {
+    pub seconds: i64,
+    pub nsec: i32,
+    pub tzoffset: i16,
+    pub tzindex: i16,
+}
+
+impl Serialize for Datetime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut data = self.seconds.to_le_bytes().to_vec();
+        // the tail is omitted when it is all zeros
+        if self.nsec != 0 || self.tzoffset != 0 || self.tzindex != 0 {
+            data.extend_from_slice(&self.nsec.to_le_bytes());
+            data.extend_from_slice(&self.tzoffset.to_le_bytes());
+            data.extend_from_slice(&self.tzindex.to_le_bytes());
+        }
+        serialize_ext(serializer, consts::MP_DATETIME, &data)
+    }
+}
+
+impl<'de> Deserialize<'de> for Datetime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = deserialize_ext(deserializer, consts::MP_DATETIME)?;
+        if data.len() != 8 && data.len() != 16 {
+            return Err(de::Error::invalid_length(data.len(), &"8 or 16 bytes"));
+        }
+
+        let mut datetime = Datetime {
+            seconds: i64::from_le_bytes(data[..8].try_into().unwrap()),
+            ..Datetime::default()
+        };
+        if data.len() == 16 {
+            datetime.nsec = i32::from_le_bytes(data[8..12].try_into().unwrap());
+            datetime.tzoffset = i16::from_le_bytes(data[12..14].try_into().unwrap());
+            datetime.tzindex = i16::from_le_bytes(data[14..16].try_into().unwrap());
+        }
+        Ok(datetime)
+    }
+}
+
+/// How adding an interval treats the end of month.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Adjust {
+    /// overflow to the next month: Jan 31 + 1 month = Mar 3
+    Excess = 0,
+    /// clamp to the end of month: Jan 31 + 1 month = Feb 28
+    #[default]
+    None = 1,
+    /// stick to the last day of month: Feb 28 + 1 month = Mar 31
+    Last = 2,
+}
+
+/// `interval`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub year: i64,
+    pub month: i64,
+    pub week: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+    pub nsec: i64,
+    pub adjust: Adjust,
+}
+
+impl Interval {
+    const ADJUST_FIELD: u8 = 8;
+
+    fn fields(&self) -> [i64; 8] {
+        [
+            self.year,
+            self.month,
+            self.week,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            self.nsec,
+        ]
+    }
+
+    fn field_mut(&mut self, field: u8) -> Option<&mut i64> {
+        Some(match field {
+            0 => &mut self.year,
+            1 => &mut self.month,
+            2 => &mut self.week,
+            3 => &mut self.day,
+            4 => &mut self.hour,
+            5 => &mut self.minute,
+            6 => &mut self.second,
+            7 => &mut self.nsec,
+            _ => return None,
+        })
+    }
+
+    fn decode(mut data: &[u8]) -> Option<Self> {
+        let mut interval = Interval::default();
+
+        let (&count, rest) = data.split_first()?;
+        data = rest;
+        for _ in 0..count {
+            let field: u8 = rmp::decode::read_int(&mut data).ok()?;
+            let value: i64 = rmp::decode::read_int(&mut data).ok()?;
+            if field == Self::ADJUST_FIELD {
+                interval.adjust = match value {
+                    0 => Adjust::Excess,
+                    1 => Adjust::None,
+                    2 => Adjust::Last,
+                    _ => return None,
+                };
+            } else {
+                *interval.field_mut(field)? = value;
+            }
+        }
+        Some(interval)
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // only non-default fields are encoded
+        let mut fields: Vec<(u8, i64)> = (0..)
+            .zip(self.fields())
+            .filter(|(_, value)| *value != 0)
+            .collect();
+        if self.adjust != Adjust::None {
+            fields.push((Self::ADJUST_FIELD, self.adjust as i64));
+        }
+
+        let mut data = vec![fields.len() as u8];
+        for (field, value) in fields {
+            rmp::encode::write_uint(&mut data, field as u64).map_err(ser::Error::custom)?;
+            rmp::encode::write_sint(&mut data, value).map_err(ser::Error::custom)?;
+        }
+        serialize_ext(serializer, consts::MP_INTERVAL, &data)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = deserialize_ext(deserializer, consts::MP_INTERVAL)?;
+        Self::decode(&data).ok_or_else(|| de::Error::custom("invalid interval"))
+    }
+}
+
+/// `box.error` object, `stack[0]` is the error itself and the rest are its causes.
+#[derive(Debug, Clone)]
+pub struct BoxError {
+    pub stack: Vec<ErrorExtra>,
+}
+
+impl fmt::Display for BoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.stack.first() {
+            Some(extra) => extra.fmt(f),
+            None => f.write_str("empty error"),
+        }
+    }
+}
+
+impl std::error::Error for BoxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.stack.first()?.source()
+    }
+}
+
+impl Serialize for BoxError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut data = Vec::new();
+        ErrorExtra::encode_stack(&self.stack, &mut data).map_err(ser::Error::custom)?;
+        serialize_ext(serializer, consts::MP_ERROR, &data)
+    }
+}
+
+impl<'de> Deserialize<'de> for BoxError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = deserialize_ext(deserializer, consts::MP_ERROR)?;
+        let stack = ErrorExtra::decode_stack(&mut data.as_slice()).map_err(de::Error::custom)?;
+        Ok(Self { stack })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(&'static str);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Adjust, BoxError, Datetime, Decimal, Interval, Uuid};
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T, ext: (i8, &[u8])) {
+        let encoded = rmp_serde::to_vec(&value).unwrap();
+        let raw = rmpv::decode::read_value(&mut encoded.as_slice()).unwrap();
+        assert_eq!(raw, rmpv::Value::Ext(ext.0, ext.1.to_vec()));
+
+        let decoded: T = rmp_serde::from_slice(&encoded).unwrap();
+        assert_eq!(decoded, value);
+
+        // rmpv::Value keeps extensions as is
+        assert_eq!(rmpv::ext::to_value(&value).unwrap(), raw);
+        let decoded: T = rmpv::ext::from_value(raw).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn decimal_test() {
+        round_trip(Decimal::new(-1234, 2), (1, &[0x02, 0x01, 0x23, 0x4d]));
+        round_trip(Decimal::new(5, 0), (1, &[0x00, 0x5c]));
+
+        let decimal: Decimal = "-12.34".parse().unwrap();
+        assert_eq!(decimal, Decimal::new(-1234, 2));
+        assert_eq!(decimal.to_string(), "-12.34");
+        assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+        assert_eq!(Decimal::new(5, -2).to_string(), "500");
+        assert!("1.2.3".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn uuid_test() {
+        let uuid: Uuid = "7170b4af-c72f-4f07-8729-08fc678543a5".parse().unwrap();
+        assert_eq!(uuid.to_string(), "7170b4af-c72f-4f07-8729-08fc678543a5");
+        round_trip(uuid, (2, &uuid.0));
+        assert!("7170b4af".parse::<Uuid>().is_err());
+    }
+
+    #[test]
+    fn datetime_test() {
+        let datetime = Datetime {
+            seconds: 1,
+            ..Datetime::default()
+        };
+        round_trip(datetime, (4, &[1, 0, 0, 0, 0, 0, 0, 0]));
+
+        let datetime = Datetime {
+            seconds: 1,
+            nsec: 2,
+            tzoffset: 180,
+            tzindex: 0,
+        };
+        round_trip(
+            datetime,
+            (4, &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 180, 0, 0, 0]),
+        );
+    }
+
+    #[test]
+    fn interval_test() {
+        let interval = Interval {
+            month: 1,
+            day: -2,
+            adjust: Adjust::Last,
+            ..Interval::default()
+        };
+        round_trip(interval, (6, &[3, 1, 1, 3, 0xfe, 8, 2]));
+        round_trip(Interval::default(), (6, &[0]));
+    }
+
+    #[test]
+    fn box_error_test() {
+        let value = rmpv::Value::Map(vec![(
+            0.into(),
+            rmpv::Value::Array(vec![rmpv::Value::Map(vec![
+                (0.into(), "ClientError".into()),
+                (3.into(), "Unknown error".into()),
+                (5.into(), 0.into()),
+            ])]),
+        )]);
+        let mut data = Vec::new();
+        rmpv::encode::write_value(&mut data, &value).unwrap();
+        let ext = rmpv::Value::Ext(3, data);
+
+        let mut encoded = Vec::new();
+        rmpv::encode::write_value(&mut encoded, &ext).unwrap();
+        let err: BoxError = rmp_serde::from_slice(&encoded).unwrap();
+        assert_eq!(err.to_string(), "ClientError: Unknown error");
+
+        let encoded = rmp_serde::to_vec(&err).unwrap();
+        let decoded: BoxError = rmp_serde::from_slice(&encoded).unwrap();
+        assert_eq!(decoded.stack[0].error_message, "Unknown error");
+    }
+}
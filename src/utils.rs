@@ -1,4 +1,8 @@
-use sharded_slab::Slab;
+use sharded_slab::{Clear, Pool, Slab, pool};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// SlabEntryGuard ensures that slab entry will be deleted after guard drops
 pub(crate) struct SlabEntryGuard<'a, T> {
@@ -20,3 +24,56 @@ impl<E> Drop for SlabEntryGuard<'_, E> {
         self.slab.remove(self.idx);
     }
 }
+
+/// PoolEntryGuard ensures that pool entry will be cleared after guard drops
+pub(crate) struct PoolEntryGuard<T: Clear + Default> {
+    /// entry id to clear
+    idx: usize,
+
+    /// pool to clear in
+    pool: Arc<Pool<T>>,
+}
+
+impl<T: Clear + Default> PoolEntryGuard<T> {
+    pub fn new(idx: usize, pool: Arc<Pool<T>>) -> Self {
+        Self { idx, pool }
+    }
+
+    pub fn get(&self) -> Option<pool::Ref<'_, T>> {
+        self.pool.get(self.idx)
+    }
+
+    /// Like [`PoolEntryGuard::get`], but the reference may be held across awaits.
+    pub fn get_owned(&self) -> Option<pool::OwnedRef<T>> {
+        self.pool.clone().get_owned(self.idx)
+    }
+}
+
+impl<T: Clear + Default> Drop for PoolEntryGuard<T> {
+    fn drop(&mut self) {
+        self.pool.clear(self.idx);
+    }
+}
+
+/// CountGuard keeps the counter incremented while the guard is alive
+pub(crate) struct CountGuard<'a> {
+    counter: &'a AtomicUsize,
+}
+
+impl<'a> CountGuard<'a> {
+    pub fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self { counter }
+    }
+}
+
+impl Drop for CountGuard<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Random number good enough for jitter and load balancing, not for cryptography.
+pub(crate) fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -4,12 +4,14 @@ box.cfg {
     wal_mode = 'none',
     wal_dir = 'data',
     snap_dir = 'data',
+    memtx_use_mvcc_engine = true,
 }
 
 box.schema.func.create('procedures.sum', { language = 'C', if_not_exists = true })
 box.schema.func.create('procedures.echo', { language = 'C', if_not_exists = true })
 box.schema.func.create('sum', { language = 'Lua', if_not_exists = true })
 box.schema.func.create('echo', { language = 'Lua', if_not_exists = true })
+box.schema.func.create('push_numbers', { language = 'Lua', if_not_exists = true })
 
 function sum(a, b)
     return { a + b }
@@ -19,6 +21,21 @@ function echo(...)
     return ...
 end
 
+function push_numbers(n)
+    for i = 1, n do
+        box.session.push(i)
+    end
+    return n
+end
+
+box.schema.space.create('test', { id = 512, if_not_exists = true })
+box.space.test:create_index('primary', { parts = { 1, 'unsigned' }, if_not_exists = true })
+box.space.test:replace { 1, 'one' }
+box.space.test:replace { 2, 'two' }
+box.space.test:replace { 3, 'three' }
+
+box.execute([[CREATE TABLE IF NOT EXISTS sql_test (id INTEGER PRIMARY KEY AUTOINCREMENT, name STRING)]])
+
 -- box.func['procedures.echo']:call{1,2}
 -- box.func['procedures.sum']:call{1,2}
 